@@ -0,0 +1,30 @@
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use protobuf::Message as _;
+
+// vendored from jaegertracing/jaeger-idl, with the OpenTelemetry protos it imports
+const QUERY_SERVICE: &str = "jaeger-idl/proto/api_v3/query_service.proto";
+const INCLUDES: [&str; 2] = ["jaeger-idl/proto", "jaeger-idl/opentelemetry-proto"];
+
+// generates `jaeger::api_v3` with a pure Rust parser, so that building needs no protoc
+fn main() -> anyhow::Result<()> {
+    println!("cargo:rerun-if-changed=jaeger-idl");
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .includes(INCLUDES)
+        .input(QUERY_SERVICE)
+        .parse_and_typecheck()?;
+    // with the imported files, which prost needs to resolve field types
+    let descriptors = FileDescriptorSet {
+        file: parsed
+            .file_descriptors
+            .iter()
+            .map(|file| Ok(Message::decode(&*file.write_to_bytes()?)?))
+            .collect::<anyhow::Result<_>>()?,
+    };
+    // the OpenTelemetry messages are those of `opentelemetry-proto`, as the collectors use
+    tonic_prost_build::configure()
+        .extern_path(".opentelemetry.proto", "::opentelemetry_proto::tonic")
+        .compile_fds(descriptors)?;
+    Ok(())
+}
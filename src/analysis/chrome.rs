@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::{jaeger::KUBO, traceparent::hex, traces::SERVICE_ATTR};
+
+use super::{
+    tree::{attributes, SpanRef, SpanTree},
+    TraceFile,
+};
+
+// lane of a span, by the component that names it
+fn component(name: &str) -> &str {
+    let prefix = name.split('.').next().unwrap_or(name);
+    match prefix {
+        "KademliaDHT" | "Dual" | "DHT" | "RoutingSystem" => "DHT",
+        _ if prefix.starts_with("Bitswap") => "Bitswap",
+        _ if prefix.starts_with("Blockservice") => "Blockservice",
+        _ => prefix,
+    }
+}
+
+// Chrome trace events of the spans as one process, with a thread per component; trace viewers
+// only show properly nested slices on a thread, so overlapping spans spill into extra threads
+fn process_events<'a>(
+    spans: impl IntoIterator<Item = SpanRef<'a>>,
+    pid: usize,
+    name: &str,
+    start: u64,
+    events: &mut Vec<Value>,
+) {
+    let micros = |nanos: u64| nanos.saturating_sub(start) as f64 / 1e3;
+    events.push(json!({
+        "ph": "M", "name": "process_name", "pid": pid, "tid": 0,
+        "args": { "name": name },
+    }));
+    // component -> lanes, each a stack of the end times of its open spans
+    let mut lanes = BTreeMap::<&str, Vec<Vec<u64>>>::new();
+    let mut tids = BTreeMap::<(&str, usize), usize>::new();
+    for span in spans {
+        let component = component(span.name());
+        let component_lanes = lanes.entry(component).or_default();
+        let lane = component_lanes.iter_mut().position(|stack| {
+            while stack.last().is_some_and(|end| *end <= span.start()) {
+                stack.pop();
+            }
+            stack.last().is_none_or(|end| *end >= span.end())
+        });
+        let lane = lane.unwrap_or_else(|| {
+            component_lanes.push(Vec::new());
+            component_lanes.len() - 1
+        });
+        component_lanes[lane].push(span.end());
+        let next_tid = tids.len() + 1;
+        let tid = *tids.entry((component, lane)).or_insert(next_tid);
+        events.push(json!({
+            "ph": "X",
+            "name": span.name(),
+            "cat": component,
+            "pid": pid,
+            "tid": tid,
+            "ts": micros(span.start()),
+            "dur": span.end().saturating_sub(span.start()) as f64 / 1e3,
+            "args": args(&span),
+        }));
+        for event in span.events() {
+            events.push(json!({
+                "ph": "i",
+                "s": "t",
+                "name": event.name,
+                "cat": component,
+                "pid": pid,
+                "tid": tid,
+                "ts": micros(event.time_unix_nano),
+                "args": attributes(&event.attributes)
+                    .into_iter()
+                    .map(|(key, value)| (key, value.to_string().into()))
+                    .collect::<Map<_, _>>(),
+            }))
+        }
+    }
+    for ((component, lane), tid) in tids {
+        let name = if lane == 0 {
+            component.to_string()
+        } else {
+            format!("{component} #{}", lane + 1)
+        };
+        events.push(json!({
+            "ph": "M", "name": "thread_name", "pid": pid, "tid": tid,
+            "args": { "name": name },
+        }));
+        events.push(json!({
+            "ph": "M", "name": "thread_sort_index", "pid": pid, "tid": tid,
+            "args": { "sort_index": tid },
+        }))
+    }
+}
+
+fn args(span: &SpanRef<'_>) -> Map<String, Value> {
+    let mut args = span
+        .attrs()
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string().into()))
+        .collect::<Map<_, _>>();
+    args.insert("trace id".into(), hex(&span.span().trace_id).into());
+    args.insert("span id".into(), hex(&span.span().span_id).into());
+    if let Some(error) = span.error() {
+        args.insert("error".into(), error.into());
+    }
+    args
+}
+
+// spans by the service that reported them, spans saved before services were kept are Kubo's
+fn by_service(spans: &SpanTree) -> BTreeMap<&str, Vec<SpanRef<'_>>> {
+    let mut services = BTreeMap::<_, Vec<_>>::new();
+    for span in spans.spans() {
+        services
+            .entry(span.str_attr(SERVICE_ATTR).unwrap_or(KUBO))
+            .or_default()
+            .push(span)
+    }
+    services
+}
+
+// converts a saved trace into Chrome trace event JSON for Perfetto or chrome://tracing, with a
+// process per service for the measured trace and another per service for the other traces in its
+// time range
+pub async fn export(path: PathBuf, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let trace = TraceFile::load(&path).await?;
+    let start = trace
+        .trace
+        .spans()
+        .chain(trace.other.spans())
+        .map(|span| span.start())
+        .min()
+        .unwrap_or_default();
+    let trace_id = trace
+        .trace
+        .roots()
+        .next()
+        .map(|span| hex(&span.span().trace_id))
+        .unwrap_or_default();
+
+    let mut events = Vec::new();
+    let mut pid = 0;
+    for (service, spans) in by_service(&trace.trace) {
+        pid += 1;
+        process_events(
+            spans,
+            pid,
+            &format!("{service} trace {trace_id}"),
+            start,
+            &mut events,
+        )
+    }
+    for (service, spans) in by_service(&trace.other) {
+        pid += 1;
+        process_events(
+            spans,
+            pid,
+            &format!("{service} other traces"),
+            start,
+            &mut events,
+        )
+    }
+    let output = output.unwrap_or(path.with_extension("chrome.json"));
+    tokio::fs::write(
+        &output,
+        serde_json::to_vec(&json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        }))?,
+    )
+    .await?;
+    println!(
+        "* Exported {} span(s) to {}",
+        trace.trace.len() + trace.other.len(),
+        output.display()
+    );
+    Ok(())
+}
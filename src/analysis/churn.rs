@@ -0,0 +1,498 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, traces::timestamp};
+
+use super::{peers::load_records, save_csv};
+
+// one run of dump-providers, `<millis>.json` and `<millis>.route.json` if explicit routing finished
+#[derive(Debug)]
+struct Snapshot {
+    millis: u64,
+    // record addresses per provider
+    addrs: BTreeMap<String, BTreeSet<String>>,
+    // addresses that explicit routing found per provider
+    route_addrs: Option<BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl Snapshot {
+    fn routable(&self) -> Option<BTreeSet<&str>> {
+        let route_addrs = self.route_addrs.as_ref()?;
+        Some(
+            route_addrs
+                .iter()
+                .filter(|(_, addrs)| !addrs.is_empty())
+                .map(|(id, _)| &**id)
+                .collect(),
+        )
+    }
+}
+
+async fn addrs(path: &Path) -> anyhow::Result<BTreeMap<String, BTreeSet<String>>> {
+    let mut addrs = BTreeMap::<_, BTreeSet<_>>::new();
+    for record in load_records(path).await? {
+        addrs.entry(record.ID).or_default().extend(record.Addrs)
+    }
+    Ok(addrs)
+}
+
+async fn snapshots(dir: &Path) -> anyhow::Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let Some(millis) = name
+            .strip_suffix(".json")
+            .and_then(|millis| millis.parse().ok())
+        else {
+            continue;
+        };
+        let route_path = path.with_extension("route.json");
+        let route_addrs = if route_path.exists() {
+            Some(addrs(&route_path).await?)
+        } else {
+            None
+        };
+        snapshots.push(Snapshot {
+            millis,
+            addrs: addrs(&path).await?,
+            route_addrs,
+        })
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.millis);
+    Ok(snapshots)
+}
+
+// last time profile-providers, which always profiles the latest dump, connected to each provider
+// by any transport, and last time it also downloaded from it
+#[derive(Debug, Default)]
+struct Dialed {
+    connected: BTreeMap<String, u64>,
+    downloaded: BTreeMap<String, u64>,
+}
+
+fn update(last: &mut BTreeMap<String, u64>, id: &str, millis: u64) {
+    let last = last.entry(id.to_string()).or_default();
+    *last = millis.max(*last)
+}
+
+async fn dialed(config: &Config, cid: &str) -> anyhow::Result<Dialed> {
+    let mut dialed = Dialed::default();
+    let dir = config.output_dir().join("profile-providers").join(cid);
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return Ok(dialed);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        // `<millis>.connect.csv` has every connect attempt, `<millis>.download.csv` only the
+        // finished downloads and `<millis>.gateway.csv` every response
+        let (millis, kind, column) = if let Some(millis) = name.strip_suffix(".connect.csv") {
+            (millis, "connect", Some("success"))
+        } else if let Some(millis) = name.strip_suffix(".download.csv") {
+            (millis, "download", None)
+        } else if let Some(millis) = name.strip_suffix(".gateway.csv") {
+            (millis, "gateway", Some("status"))
+        } else {
+            continue;
+        };
+        let Ok(millis) = millis.parse::<u64>() else {
+            continue;
+        };
+        let content = tokio::fs::read_to_string(&path).await?;
+        let mut lines = content.lines().peekable();
+        // download CSVs of runs before they had a header start right with the rows
+        let header = lines.next_if(|line| line.starts_with("peer id,"));
+        let column = match column {
+            Some(column) => {
+                let position =
+                    header.and_then(|header| header.split(',').position(|field| field == column));
+                let Some(position) = position else {
+                    println!("! No {column} column in {}", path.display());
+                    continue;
+                };
+                Some(position)
+            }
+            None => None,
+        };
+        for line in lines {
+            let fields = line.split(',').collect::<Vec<_>>();
+            let Some(id) = fields.first() else {
+                continue;
+            };
+            let value = column.and_then(|column| fields.get(column).copied());
+            match kind {
+                "connect" => {
+                    if value == Some("true") {
+                        update(&mut dialed.connected, id, millis)
+                    }
+                }
+                _ => {
+                    let success = kind == "download"
+                        || value
+                            .and_then(|status| status.parse::<u16>().ok())
+                            .is_some_and(|status| (200..300).contains(&status));
+                    if success {
+                        // a download needs a connection, also for runs before connects were saved
+                        update(&mut dialed.connected, id, millis);
+                        update(&mut dialed.downloaded, id, millis)
+                    }
+                }
+            }
+        }
+    }
+    Ok(dialed)
+}
+
+// presence of a provider across the snapshots
+#[derive(Debug, Default)]
+struct Peer {
+    snapshots: usize,
+    // first and last snapshot of each run of consecutive snapshots listing the provider
+    sessions: Vec<(u64, u64)>,
+    // still listed by the latest snapshot
+    open: bool,
+    // between consecutive snapshots that both have record addresses for the provider
+    addr_changes: usize,
+    // between consecutive snapshots whose explicit routing both found addresses for the provider
+    route_addr_changes: usize,
+}
+
+impl Peer {
+    fn longest_session(&self) -> u64 {
+        self.sessions
+            .iter()
+            .map(|(start, end)| end - start)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+fn hours(millis: u64) -> f64 {
+    millis as f64 / 3_600_000.
+}
+
+// median, mean and maximum
+fn describe(values: &mut [u64]) -> String {
+    if values.is_empty() {
+        return "none".into();
+    }
+    values.sort();
+    format!(
+        "median {:.2}h mean {:.2}h max {:.2}h",
+        hours(values[values.len() / 2]),
+        hours(values.iter().sum::<u64>() / values.len() as u64),
+        hours(values[values.len() - 1])
+    )
+}
+
+// churn of the providers of one CID, over all of its dumps in `dir`
+async fn report(config: &Config, dir: &Path, csv_content: &mut String) -> anyhow::Result<()> {
+    let cid = dir
+        .file_name()
+        .ok_or(anyhow::anyhow!("No CID in {}", dir.display()))?
+        .to_string_lossy();
+    let snapshots = snapshots(dir).await?;
+    let (Some(first), Some(latest)) = (snapshots.first(), snapshots.last()) else {
+        println!("! No dump in {}", dir.display());
+        return Ok(());
+    };
+    let dialed = dialed(config, &cid).await?;
+    println!(
+        "* {} snapshot(s) of {cid} over {:.2}h",
+        snapshots.len(),
+        hours(latest.millis - first.millis)
+    );
+
+    println!(
+        "{:<32} {:>9} {:>8} {:>11}",
+        "time", "providers", "appeared", "disappeared"
+    );
+    let mut peers = BTreeMap::<&str, Peer>::new();
+    let mut previous = None::<&Snapshot>;
+    for snapshot in &snapshots {
+        let (appeared, disappeared) = match previous {
+            Some(previous) => (
+                snapshot
+                    .addrs
+                    .keys()
+                    .filter(|id| !previous.addrs.contains_key(*id))
+                    .count()
+                    .to_string(),
+                previous
+                    .addrs
+                    .keys()
+                    .filter(|id| !snapshot.addrs.contains_key(*id))
+                    .count()
+                    .to_string(),
+            ),
+            None => Default::default(),
+        };
+        println!(
+            "{:<32} {:>9} {:>8} {:>11}",
+            timestamp(snapshot.millis * 1_000_000).to_string(),
+            snapshot.addrs.len(),
+            appeared,
+            disappeared
+        );
+        for (id, addrs) in &snapshot.addrs {
+            let peer = peers.entry(id).or_default();
+            peer.snapshots += 1;
+            let previous_addrs = previous.and_then(|previous| previous.addrs.get(id));
+            match (previous_addrs, peer.sessions.last_mut()) {
+                (Some(previous_addrs), Some((_, end))) => {
+                    *end = snapshot.millis;
+                    if !previous_addrs.is_empty() && !addrs.is_empty() && previous_addrs != addrs {
+                        peer.addr_changes += 1
+                    }
+                    let route_addrs = |snapshot: &Snapshot| {
+                        snapshot
+                            .route_addrs
+                            .as_ref()
+                            .and_then(|route_addrs| route_addrs.get(id))
+                            .filter(|addrs| !addrs.is_empty())
+                            .cloned()
+                    };
+                    if let (Some(previous_route_addrs), Some(route_addrs)) =
+                        (previous.and_then(route_addrs), route_addrs(snapshot))
+                    {
+                        if previous_route_addrs != route_addrs {
+                            peer.route_addr_changes += 1
+                        }
+                    }
+                }
+                _ => peer.sessions.push((snapshot.millis, snapshot.millis)),
+            }
+        }
+        previous = Some(snapshot)
+    }
+    for id in latest.addrs.keys() {
+        if let Some(peer) = peers.get_mut(&**id) {
+            peer.open = true
+        }
+    }
+
+    // a session still open at the latest snapshot is only known to last at least this long
+    let (mut closed, mut open) = (Vec::new(), Vec::new());
+    for peer in peers.values() {
+        for (i, (start, end)) in peer.sessions.iter().enumerate() {
+            if peer.open && i == peer.sessions.len() - 1 {
+                open.push(end - start)
+            } else {
+                closed.push(end - start)
+            }
+        }
+    }
+    println!(
+        "* {} ended session(s): {}",
+        closed.len(),
+        describe(&mut closed)
+    );
+    println!(
+        "* {} session(s) open at the latest snapshot: {}",
+        open.len(),
+        describe(&mut open)
+    );
+    for (kind, changes) in [
+        (
+            "record",
+            (|peer: &Peer| peer.addr_changes) as fn(&Peer) -> usize,
+        ),
+        ("explicit routing", |peer: &Peer| peer.route_addr_changes),
+    ] {
+        let mut changed = peers
+            .iter()
+            .filter(|(_, peer)| changes(peer) > 0)
+            .collect::<Vec<_>>();
+        changed.sort_by_key(|(_, peer)| std::cmp::Reverse(changes(peer)));
+        println!(
+            "* {} of {} provider(s) changed {kind} addresses, {} time(s) in total",
+            changed.len(),
+            peers.len(),
+            changed.iter().map(|(_, peer)| changes(peer)).sum::<usize>()
+        );
+        for (id, peer) in changed.iter().take(5) {
+            println!("  {id} {} time(s)", changes(peer))
+        }
+    }
+
+    // how useful the records of each dump are by the latest one
+    let latest_routable = latest.routable();
+    let after_latest = |last: &BTreeMap<String, u64>, snapshot: &Snapshot| {
+        snapshot
+            .addrs
+            .keys()
+            .filter(|id| last.get(*id).is_some_and(|last| *last >= latest.millis))
+            .count()
+    };
+    println!(
+        "{:<32} {:>9} {:>6} {:>8} {:>6} {:>10}",
+        "dump", "providers", "listed", "routable", "dialed", "downloaded"
+    );
+    for snapshot in &snapshots {
+        let listed = snapshot
+            .addrs
+            .keys()
+            .filter(|id| latest.addrs.contains_key(*id))
+            .count();
+        let routable = latest_routable
+            .as_ref()
+            .map(|routable| {
+                snapshot
+                    .addrs
+                    .keys()
+                    .filter(|id| routable.contains(id.as_str()))
+                    .count()
+                    .to_string()
+            })
+            .unwrap_or("-".into());
+        println!(
+            "{:<32} {:>9} {:>6} {:>8} {:>6} {:>10}",
+            timestamp(snapshot.millis * 1_000_000).to_string(),
+            snapshot.addrs.len(),
+            listed,
+            routable,
+            after_latest(&dialed.connected, snapshot),
+            after_latest(&dialed.downloaded, snapshot)
+        );
+    }
+
+    for (id, peer) in &peers {
+        writeln!(
+            csv_content,
+            "{cid},{id},{},{},{},{},{},{},{},{},{},{},{}",
+            peer.snapshots,
+            peer.sessions.len(),
+            hours(peer.longest_session()),
+            peer.open,
+            peer.addr_changes,
+            peer.route_addr_changes,
+            peer.sessions
+                .first()
+                .map(|(start, _)| *start)
+                .unwrap_or_default(),
+            peer.sessions
+                .last()
+                .map(|(_, end)| *end)
+                .unwrap_or_default(),
+            latest_routable
+                .as_ref()
+                .is_some_and(|routable| routable.contains(*id)),
+            dialed.connected.contains_key(*id),
+            dialed.downloaded.contains_key(*id),
+        )?;
+    }
+    Ok(())
+}
+
+// appearing and disappearing providers, session lengths, address changes and how many providers
+// of old dumps are still listed, routable, dialed and downloaded from, over every dump of each CID
+pub async fn run(config: Config, dirs: Vec<PathBuf>, csv: Option<PathBuf>) -> anyhow::Result<()> {
+    let dirs = if dirs.is_empty() {
+        // hello world DAG
+        let cid = config.cid("baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea");
+        vec![config.output_dir().join("dump-providers").join(cid)]
+    } else {
+        dirs
+    };
+    let mut csv_content = String::new();
+    writeln!(
+        &mut csv_content,
+        "cid,peer id,snapshots,sessions,longest session hours,open,address changes,route address changes,first seen millis,last seen millis,routable,dialed,downloaded"
+    )?;
+    for dir in dirs {
+        report(&config, &dir, &mut csv_content).await?
+    }
+    if let Some(csv) = csv {
+        save_csv(csv, csv_content).await?
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+    const HOUR: u64 = 3_600_000;
+
+    fn records(records: &[(&str, &str)]) -> String {
+        serde_json::to_string(
+            &records
+                .iter()
+                .map(|(id, addr)| serde_json::json!({"ID": id, "Addrs": [addr]}))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    // A is listed throughout, B until the second dump and C again at the latest
+    #[tokio::test]
+    async fn sessions_and_dials() {
+        let output_dir = std::env::temp_dir().join(format!("izuko-churn-{}", std::process::id()));
+        let dumps = output_dir.join("dump-providers").join(CID);
+        let profiles = output_dir.join("profile-providers").join(CID);
+        tokio::fs::create_dir_all(&dumps).await.unwrap();
+        tokio::fs::create_dir_all(&profiles).await.unwrap();
+        for (millis, extension, content) in [
+            (
+                0,
+                "json",
+                records(&[("A", "/a1"), ("B", "/b"), ("C", "/c")]),
+            ),
+            (HOUR, "json", records(&[("A", "/a1"), ("B", "/b")])),
+            (HOUR, "route.json", records(&[("A", "/r1")])),
+            (2 * HOUR, "json", records(&[("A", "/a2"), ("C", "/c")])),
+            (2 * HOUR, "route.json", records(&[("A", "/r2")])),
+        ] {
+            tokio::fs::write(dumps.join(format!("{millis}.{extension}")), content)
+                .await
+                .unwrap()
+        }
+        for (millis, extension, content) in [
+            // before the header was added
+            (HOUR + 1, "download.csv", "B,local,false,1.5\n".to_string()),
+            (
+                2 * HOUR + 1,
+                "connect.csv",
+                format!(
+                    "peer id,region,route,{}\nA,local,false,tcp,/a2,true,false,/a2,0.1\nC,local,false,tcp,/c,false,false,,0.1\n",
+                    crate::transport::Attempt::HEADER
+                ),
+            ),
+            (
+                2 * HOUR + 2,
+                "gateway.csv",
+                format!(
+                    "peer id,region,route,{}\nA,local,false,504,0,30,0\nC,local,false,200,0.5,1,100\n",
+                    crate::gateway::Response::HEADER
+                ),
+            ),
+        ] {
+            tokio::fs::write(profiles.join(format!("{millis}.{extension}")), content)
+                .await
+                .unwrap()
+        }
+
+        let config = Config {
+            output_dir: Some(output_dir.clone()),
+            ..Default::default()
+        };
+        let mut csv_content = String::new();
+        let result = report(&config, &dumps, &mut csv_content).await;
+        tokio::fs::remove_dir_all(&output_dir).await.unwrap();
+        result.unwrap();
+        assert_eq!(
+            csv_content.lines().collect::<Vec<_>>(),
+            [
+                format!("{CID},A,3,1,2,true,1,1,0,{},true,true,false", 2 * HOUR),
+                format!("{CID},B,2,1,1,false,0,0,0,{HOUR},false,true,true"),
+                format!("{CID},C,2,2,0,true,0,0,0,{},false,true,true", 2 * HOUR),
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::traceparent::hex;
+
+use super::{seconds, tree::SpanRef, TraceFile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    ProviderSearch,
+    Dialing,
+    // Bitswap waiting until the first block arrives
+    FirstBlockWait,
+    BlockTransfer,
+    // the command itself is on the critical path, i.e. no traced work is
+    Idle,
+    Other,
+}
+
+impl Phase {
+    pub const ALL: [Self; 6] = [
+        Self::ProviderSearch,
+        Self::Dialing,
+        Self::FirstBlockWait,
+        Self::BlockTransfer,
+        Self::Idle,
+        Self::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ProviderSearch => "provider search",
+            Self::Dialing => "dialing",
+            Self::FirstBlockWait => "first block wait",
+            Self::BlockTransfer => "block transfer",
+            Self::Idle => "idle",
+            Self::Other => "other",
+        }
+    }
+}
+
+// a stretch of the critical path spent in the span itself, i.e. in none of its children
+#[derive(Debug, Clone, Serialize)]
+pub struct Segment {
+    pub span: String,
+    pub trace_id: String,
+    pub span_id: String,
+    pub phase: Phase,
+    pub start_nano: u64,
+    pub end_nano: u64,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Breakdown {
+    pub cid: String,
+    pub start_nano: u64,
+    pub total: f64,
+    pub segments: Vec<Segment>,
+    // seconds per phase
+    pub phases: BTreeMap<Phase, f64>,
+}
+
+fn phase(span: &SpanRef<'_>, is_root: bool) -> Phase {
+    let name = span.name();
+    if name.contains("Dial") || name.contains("Connect") {
+        Phase::Dialing
+    } else if name.contains("FindProviders")
+        || name.starts_with("KademliaDHT")
+        || name.starts_with("Dual")
+        || name.starts_with("Routing")
+        || name.contains("ProviderQueryManager")
+    {
+        Phase::ProviderSearch
+    } else if name.starts_with("Bitswap") || name.starts_with("Blockservice") {
+        Phase::BlockTransfer
+    } else if is_root || name.starts_with("CoreAPI") || name.starts_with("corehttp") {
+        Phase::Idle
+    } else {
+        Phase::Other
+    }
+}
+
+#[derive(Debug, Default)]
+struct CriticalPath<'a> {
+    // lookups for the CID that kubo runs in traces of their own, which any concurrent span may
+    // be waiting on, e.g. a Bitswap session
+    lookups: Vec<SpanRef<'a>>,
+    // lookups being walked into, which are not candidates again further down, otherwise two
+    // overlapping lookups would each wait on the other forever
+    walking: Vec<SpanRef<'a>>,
+    // in reverse time order
+    path: Vec<(SpanRef<'a>, u64, u64)>,
+}
+
+impl<'a> CriticalPath<'a> {
+    // a lookup is only waited on until it finds a provider
+    fn end(&self, span: &SpanRef<'a>) -> u64 {
+        if self.lookups.contains(span) {
+            if let Some(found) = span.event("found provider") {
+                return found.time_unix_nano.min(span.end());
+            }
+        }
+        span.end()
+    }
+
+    // walks back from `ceil`, descending into the child that finishes last each time, so the
+    // time of `span` outside `[floor, ceil]` is not attributed
+    fn walk(&mut self, span: SpanRef<'a>, floor: u64, ceil: u64) {
+        let start = span.start().max(floor);
+        let mut cursor = self.end(&span).min(ceil);
+        let is_lookup = self.lookups.contains(&span);
+        if is_lookup {
+            self.walking.push(span)
+        }
+        let lookups = self
+            .lookups
+            .iter()
+            .filter(|lookup| !self.walking.contains(lookup));
+        let mut children = span.children().chain(lookups.copied()).collect::<Vec<_>>();
+        while cursor > start {
+            let next = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| child.start() < cursor && self.end(child) > start)
+                .max_by_key(|(_, child)| (self.end(child).min(cursor), child.start()))
+                .map(|(i, _)| i);
+            let Some(next) = next else {
+                self.path.push((span, start, cursor));
+                break;
+            };
+            let child = children.swap_remove(next);
+            let child_end = self.end(&child).min(cursor);
+            if child_end < cursor {
+                self.path.push((span, child_end, cursor))
+            }
+            self.walk(child, start, child_end);
+            cursor = child.start().max(start)
+        }
+        if is_lookup {
+            self.walking.pop();
+        }
+    }
+}
+
+impl Breakdown {
+    pub fn new(trace: &TraceFile) -> anyhow::Result<Self> {
+        let get_span = trace
+            .trace
+            .named("CoreAPI.UnixfsAPI.Get")
+            .next()
+            .ok_or(anyhow::anyhow!("No CoreAPI.UnixfsAPI.Get span"))?;
+        let cid = get_span
+            .str_attr("path")
+            .and_then(|path| path.split('/').next_back())
+            .ok_or(anyhow::anyhow!(
+                "No path attribute in CoreAPI.UnixfsAPI.Get span"
+            ))?;
+        // the whole command if traced
+        let root = get_span
+            .ancestors()
+            .find(|span| span.name() == "corehttp.cmdsHandler")
+            .unwrap_or(get_span);
+        let lookups = trace
+            .other
+            .roots()
+            .filter(|span| {
+                std::iter::once(*span)
+                    .chain(span.descendants())
+                    .any(|span| span.str_attr("key") == Some(cid))
+            })
+            .collect::<Vec<_>>();
+        let first_block = trace
+            .trace
+            .named("Bitswap.NotifyNewBlocks")
+            .map(|span| span.start())
+            .min();
+
+        let mut critical_path = CriticalPath {
+            lookups,
+            ..Default::default()
+        };
+        critical_path.walk(root, root.start(), root.end());
+        let mut segments = Vec::<Segment>::new();
+        for (span, start, end) in critical_path.path.into_iter().rev() {
+            let phase = phase(&span, span == root);
+            // Bitswap before the first block is waiting rather than transferring
+            let split = match (phase, first_block) {
+                (Phase::BlockTransfer, Some(first_block)) if start < first_block => {
+                    vec![
+                        (Phase::FirstBlockWait, start, end.min(first_block)),
+                        (Phase::BlockTransfer, first_block, end),
+                    ]
+                }
+                _ => vec![(phase, start, end)],
+            };
+            for (phase, start, end) in split {
+                if start >= end {
+                    continue;
+                }
+                let span_id = hex(&span.span().span_id);
+                match segments.last_mut() {
+                    Some(last) if last.span_id == span_id && last.phase == phase => {
+                        last.end_nano = end;
+                        last.seconds = seconds(last.start_nano, end)
+                    }
+                    _ => segments.push(Segment {
+                        span: span.name().into(),
+                        trace_id: hex(&span.span().trace_id),
+                        span_id,
+                        phase,
+                        start_nano: start,
+                        end_nano: end,
+                        seconds: seconds(start, end),
+                    }),
+                }
+            }
+        }
+        let phases = Phase::ALL
+            .into_iter()
+            .map(|phase| {
+                let total = segments
+                    .iter()
+                    .filter(|segment| segment.phase == phase)
+                    .fold(0., |total, segment| total + segment.seconds);
+                (phase, total)
+            })
+            .collect();
+        Ok(Self {
+            cid: cid.into(),
+            start_nano: root.start(),
+            total: seconds(root.start(), root.end()),
+            segments,
+            phases,
+        })
+    }
+
+    pub fn print(&self) {
+        const WIDTH: usize = 50;
+        let scale = WIDTH as f64 / self.total.max(f64::EPSILON);
+        println!("* Critical path of {} in {:.3}s", self.cid, self.total);
+        for segment in &self.segments {
+            let offset = seconds(self.start_nano, segment.start_nano);
+            let indent = ((offset * scale) as usize).min(WIDTH - 1);
+            let bar = ((segment.seconds * scale).round() as usize).clamp(1, WIDTH - indent);
+            println!(
+                "{:>8.3}s {:>8.3}s |{}{}{}| {:<16} {}",
+                offset,
+                segment.seconds,
+                " ".repeat(indent),
+                "#".repeat(bar),
+                " ".repeat(WIDTH - indent - bar),
+                segment.phase.name(),
+                segment.span
+            )
+        }
+        for (phase, total) in &self.phases {
+            println!(
+                "{:>18.3}s {:>5.1}% {}",
+                total,
+                total / self.total.max(f64::EPSILON) * 100.,
+                phase.name()
+            )
+        }
+    }
+}
+
+pub async fn run(path: PathBuf, json: Option<PathBuf>) -> anyhow::Result<()> {
+    let breakdown = Breakdown::new(&TraceFile::load(&path).await?)?;
+    breakdown.print();
+    if let Some(json) = json {
+        tokio::fs::write(&json, serde_json::to_vec_pretty(&breakdown)?).await?;
+        println!("* Saved breakdown to {}", json.display())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::{
+        common::v1::{any_value, AnyValue, KeyValue},
+        trace::v1::Span,
+    };
+
+    use crate::analysis::tree::SpanTree;
+
+    use super::*;
+
+    const CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+
+    fn span(trace: u8, id: u8, parent: Option<u8>, name: &str, start: u64, end: u64) -> Span {
+        Span {
+            trace_id: vec![trace; 16],
+            span_id: vec![id; 8],
+            parent_span_id: parent.map(|parent| vec![parent; 8]).unwrap_or_default(),
+            name: name.into(),
+            start_time_unix_nano: start,
+            end_time_unix_nano: end,
+            ..Default::default()
+        }
+    }
+
+    fn attr(mut span: Span, key: &str, value: &str) -> Span {
+        span.attributes.push(KeyValue {
+            key: key.into(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.into())),
+            }),
+        });
+        span
+    }
+
+    // two lookups for the CID overlapping each other and the retrieval, neither finds a provider
+    #[test]
+    fn overlapping_lookups() {
+        let trace = TraceFile {
+            path: Default::default(),
+            trace: SpanTree::new([
+                attr(
+                    span(1, 1, None, "CoreAPI.UnixfsAPI.Get", 0, 100),
+                    "path",
+                    &format!("/ipfs/{CID}"),
+                ),
+                span(1, 2, Some(1), "Bitswap.GetBlock", 10, 90),
+            ]),
+            other: SpanTree::new([
+                attr(
+                    span(2, 3, None, "Dual.FindProvidersAsync", 20, 80),
+                    "key",
+                    CID,
+                ),
+                attr(
+                    span(3, 4, None, "Dual.FindProvidersAsync", 30, 70),
+                    "key",
+                    CID,
+                ),
+            ]),
+        };
+        let breakdown = Breakdown::new(&trace).unwrap();
+        assert_eq!(breakdown.segments.first().unwrap().start_nano, 0);
+        assert_eq!(breakdown.segments.last().unwrap().end_nano, 100);
+        assert!(breakdown
+            .segments
+            .windows(2)
+            .all(|segments| segments[0].end_nano <= segments[1].start_nano));
+        assert!(breakdown.phases[&Phase::ProviderSearch] > 0.);
+    }
+}
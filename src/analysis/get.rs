@@ -0,0 +1,141 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::config::Config;
+
+use super::{save_csv, seconds, trace_dirs, trace_files, tree::Query, TraceFile};
+
+// latencies of a `get` trace in seconds, as `report_trace` of traces-get.ipynb
+#[derive(Debug)]
+pub struct Report {
+    pub cid: String,
+    pub start_nano: u64,
+    // from the command to the provider lookup of the CID
+    pub find_delay: f64,
+    // from the lookup to the first provider found
+    pub find: f64,
+    // from the first provider found to the first block arrival
+    pub first_block: f64,
+    // from the Bitswap session to the command end
+    pub transfer: f64,
+    pub total: f64,
+}
+
+impl Report {
+    pub const HEADER: &str = "cid,start nano,find delay,find,first block,transfer,total";
+
+    pub fn new(trace: &TraceFile) -> anyhow::Result<Self> {
+        let spans = &trace.trace;
+        let get_span = spans
+            .named("CoreAPI.UnixfsAPI.Get")
+            .next()
+            .ok_or(anyhow::anyhow!("No CoreAPI.UnixfsAPI.Get span"))?;
+        let cid = get_span
+            .str_attr("path")
+            .and_then(|path| path.split('/').next_back())
+            .ok_or(anyhow::anyhow!(
+                "No path attribute in CoreAPI.UnixfsAPI.Get span"
+            ))?;
+        let cmd_span = spans
+            .named("corehttp.cmdsHandler")
+            .next()
+            .ok_or(anyhow::anyhow!(
+                "No corehttp.cmdsHandler span in trace of {cid}"
+            ))?;
+        let (start_nano, end_nano) = (cmd_span.start(), cmd_span.end());
+        let transfer_start_nano = spans
+            .named("Blockservice.Session.GetBlocks")
+            .next()
+            .ok_or(anyhow::anyhow!("No GetBlocks span in trace of {cid}"))?
+            .start();
+        let first_block_nano = spans
+            .named("Bitswap.NotifyNewBlocks")
+            .map(|span| span.start())
+            .min()
+            .ok_or(anyhow::anyhow!("No NotifyNewBlocks span in trace of {cid}"))?;
+
+        let query = Query::new()
+            .name("Dual.FindProvidersAsync")
+            .attr("key", cid);
+        let find_spans = trace.find_all(&query).collect::<Vec<_>>();
+        let [find_span] = &find_spans[..] else {
+            anyhow::ensure!(
+                !find_spans.is_empty(),
+                "No FindProviders span in trace of {cid}"
+            );
+            anyhow::bail!("Multiple FindProviders spans in trace of {cid}")
+        };
+        let find_start_nano = find_span.start();
+        let find_end_nano = find_span
+            .event("found provider")
+            .ok_or(anyhow::anyhow!("No FindProvider event in trace of {cid}"))?
+            .time_unix_nano;
+
+        Ok(Self {
+            cid: cid.into(),
+            start_nano,
+            find_delay: seconds(start_nano, find_start_nano),
+            find: seconds(find_start_nano, find_end_nano),
+            first_block: seconds(find_end_nano, first_block_nano),
+            transfer: seconds(transfer_start_nano, end_nano),
+            total: seconds(start_nano, end_nano),
+        })
+    }
+}
+
+// the traces of get-once and get-hot unless `dirs` are given
+pub async fn run(config: Config, dirs: Vec<PathBuf>, csv: Option<PathBuf>) -> anyhow::Result<()> {
+    let dirs = trace_dirs(&config, dirs, &["get-once/traces", "get-hot/traces"])?;
+    let mut reports = Vec::new();
+    let mut missing = 0;
+    for dir in dirs {
+        for path in trace_files(&dir).await? {
+            match Report::new(&TraceFile::load(&path).await?) {
+                Ok(report) => reports.push(report),
+                Err(err) => {
+                    println!("! {err} ({})", path.display());
+                    missing += 1
+                }
+            }
+        }
+    }
+    println!(
+        "* Analyzed {} trace(s), {missing} skipped for missing spans",
+        reports.len()
+    );
+
+    println!(
+        "{:<62} {:>10} {:>10} {:>12} {:>10} {:>10}",
+        "cid", "find delay", "find", "first block", "transfer", "total"
+    );
+    for report in &reports {
+        println!(
+            "{:<62} {:>10.3} {:>10.3} {:>12.3} {:>10.3} {:>10.3}",
+            report.cid,
+            report.find_delay,
+            report.find,
+            report.first_block,
+            report.transfer,
+            report.total
+        )
+    }
+
+    if let Some(path) = csv {
+        let mut csv_content = String::new();
+        writeln!(&mut csv_content, "{}", Report::HEADER)?;
+        for report in &reports {
+            writeln!(
+                &mut csv_content,
+                "{},{},{},{},{},{},{}",
+                report.cid,
+                report.start_nano,
+                report.find_delay,
+                report.find,
+                report.first_block,
+                report.transfer,
+                report.total
+            )?
+        }
+        save_csv(path, csv_content).await?
+    }
+    Ok(())
+}
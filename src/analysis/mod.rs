@@ -0,0 +1,227 @@
+pub mod chrome;
+pub mod churn;
+pub mod critical_path;
+pub mod get;
+pub mod peers;
+pub mod provide;
+pub mod record;
+pub mod spans;
+pub mod tree;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, status::StatusCode, Span};
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    geoip::{AddrClass, PeerLocation},
+    traceparent::hex,
+};
+
+use tree::{Query, SpanRef, SpanTree};
+
+// a trace saved by `traces::save`, i.e. `<start time>.json` and its `<start time>_other.json`, or
+// a `<start time>.record.json` retrieval record with its linked traces as the other ones
+#[derive(Debug)]
+pub struct TraceFile {
+    pub path: PathBuf,
+    pub trace: SpanTree,
+    // spans of the other traces in its time range
+    pub other: SpanTree,
+}
+
+impl TraceFile {
+    pub async fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        if path.to_string_lossy().ends_with(".record.json") {
+            return Ok(record::RetrievalRecord::load(&path).await?.trace_file(path));
+        }
+        let spans = load_spans(&path).await?;
+        let other_path = path.with_file_name(format!(
+            "{}_other.json",
+            path.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        let (spans, other_spans) = if other_path.exists() {
+            (spans, load_spans(&other_path).await?)
+        } else {
+            split_traces(spans)
+        };
+        Ok(Self {
+            path,
+            trace: SpanTree::new(spans),
+            other: SpanTree::new(other_spans),
+        })
+    }
+
+    // matching spans of both the trace and the other traces
+    pub fn find_all<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = SpanRef<'a>> {
+        self.trace.find(query).chain(self.other.find(query))
+    }
+}
+
+// the spans of the measured trace, i.e. the one of the command if any or else the earliest, and
+// those of the other traces, which retrieve-traces.py now saves into the trace file as well
+fn split_traces(spans: Vec<Span>) -> (Vec<Span>, Vec<Span>) {
+    let trace_id = spans
+        .iter()
+        .find(|span| span.name == "corehttp.cmdsHandler")
+        .or(spans.iter().min_by_key(|span| span.start_time_unix_nano))
+        .map(|span| span.trace_id.clone())
+        .unwrap_or_default();
+    spans
+        .into_iter()
+        .partition(|span| span.trace_id == trace_id)
+}
+
+// OTLP JSON spans, either as saved by `traces::save` or by the former retrieve-traces.py, whose
+// Jaeger JSON has base64 IDs and enum names
+pub async fn load_spans(path: &Path) -> anyhow::Result<Vec<Span>> {
+    let mut spans = serde_json::from_slice::<Value>(&tokio::fs::read(path).await?)?;
+    for span in spans.as_array_mut().into_iter().flatten() {
+        normalize(span)
+    }
+    serde_json::from_value(spans).map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+}
+
+fn normalize(span: &mut Value) {
+    fn id(id: &mut Value) {
+        if let Some(encoded) = id.as_str() {
+            let is_hex =
+                encoded.len() % 16 == 0 && encoded.bytes().all(|byte| byte.is_ascii_hexdigit());
+            if let (false, Ok(bytes)) = (is_hex, STANDARD.decode(encoded)) {
+                *id = hex(&bytes).into()
+            }
+        }
+    }
+    for key in ["traceId", "spanId", "parentSpanId"] {
+        span.get_mut(key).map(id);
+    }
+    for link in span
+        .get_mut("links")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        link.get_mut("traceId").map(id);
+        link.get_mut("spanId").map(id);
+    }
+    if let Some(kind) = span.get_mut("kind") {
+        if let Some(value) = kind.as_str().and_then(SpanKind::from_str_name) {
+            *kind = (value as i32).into()
+        }
+    }
+    if let Some(status) = span.get_mut("status").and_then(Value::as_object_mut) {
+        match status.get("code") {
+            Some(Value::String(code)) => {
+                if let Some(value) = StatusCode::from_str_name(code) {
+                    status.insert("code".into(), (value as i32).into());
+                }
+            }
+            // only failed spans have a status message
+            None if status.contains_key("message") => {
+                status.insert("code".into(), (StatusCode::Error as i32).into());
+            }
+            _ => {}
+        }
+    }
+}
+
+// `dirs`, or the existing ones of `defaults` in the output directory
+pub fn trace_dirs(
+    config: &Config,
+    dirs: Vec<PathBuf>,
+    defaults: &[&str],
+) -> anyhow::Result<Vec<PathBuf>> {
+    if !dirs.is_empty() {
+        return Ok(dirs);
+    }
+    let dirs = defaults
+        .iter()
+        .map(|dir| config.output_dir().join(dir))
+        .filter(|dir| dir.is_dir())
+        .collect::<Vec<_>>();
+    anyhow::ensure!(!dirs.is_empty(), "No trace directory");
+    Ok(dirs)
+}
+
+// trace files in `dir` in start time order, without the files derived from them
+pub async fn trace_files(dir: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.ends_with(".json")
+            && !["_other.json", ".record.json", ".chrome.json"]
+                .iter()
+                .any(|suffix| name.ends_with(suffix))
+        {
+            paths.push(path)
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+pub async fn save_csv(path: impl AsRef<Path>, csv_content: String) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?
+    }
+    tokio::fs::write(path, csv_content).await?;
+    println!("* Saved report to {}", path.display());
+    Ok(())
+}
+
+// a CSV field that may contain commas and quotes
+pub fn quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+pub const LOCATION_HEADER: &str = "class,ip,country,city,asn,org";
+
+pub fn location_fields(peer: &PeerLocation) -> String {
+    let location = &peer.location;
+    format!(
+        "{},{},{},{},{},{}",
+        peer.class.name(),
+        peer.ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        quote(location.country.as_deref().unwrap_or_default()),
+        quote(location.city.as_deref().unwrap_or_default()),
+        location.asn.map(|asn| asn.to_string()).unwrap_or_default(),
+        quote(location.org.as_deref().unwrap_or_default())
+    )
+}
+
+// peers per address class, and located ones per country
+pub fn summarize<'a>(peers: impl IntoIterator<Item = &'a PeerLocation>) {
+    let mut classes = HashMap::<AddrClass, usize>::new();
+    let mut countries = HashMap::<&str, usize>::new();
+    for peer in peers {
+        *classes.entry(peer.class).or_default() += 1;
+        if let Some(country) = &peer.location.country {
+            *countries.entry(country).or_default() += 1
+        }
+    }
+    let mut classes = classes.into_iter().collect::<Vec<_>>();
+    classes.sort();
+    for (class, count) in classes {
+        println!("* {count} peer(s) with {} address", class.name())
+    }
+    let mut countries = countries.into_iter().collect::<Vec<_>>();
+    countries.sort_by(|(country, count), (other_country, other_count)| {
+        other_count.cmp(count).then(country.cmp(other_country))
+    });
+    for (country, count) in countries {
+        println!("* {count} peer(s) in {country}")
+    }
+}
+
+pub fn seconds(start: u64, end: u64) -> f64 {
+    (end as i64 - start as i64) as f64 / 1e9
+}
@@ -0,0 +1,88 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, geoip::GeoIp, routing::FindProvsResponse};
+
+use super::{location_fields, save_csv, summarize, LOCATION_HEADER};
+
+// provider records saved by dump-providers, i.e. `<time>.json` and `<time>.route.json`
+pub(super) async fn load_records(path: &Path) -> anyhow::Result<Vec<FindProvsResponse>> {
+    serde_json::from_slice(&tokio::fs::read(path).await?)
+        .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+}
+
+// addresses of every provider dumped for `cid`, to locate the IDs that find-providers saves
+async fn address_book(config: &Config, cid: &str) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let mut addrs = HashMap::<_, Vec<_>>::new();
+    let dir = config.output_dir().join("dump-providers").join(cid);
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        println!("! No dumped providers in {}", dir.display());
+        return Ok(addrs);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.ends_with(".json") || name.ends_with(".events.json") {
+            continue;
+        }
+        for record in load_records(&path).await? {
+            let peer_addrs = addrs.entry(record.ID).or_default();
+            for addr in record.Addrs {
+                if !peer_addrs.contains(&addr) {
+                    peer_addrs.push(addr)
+                }
+            }
+        }
+    }
+    Ok(addrs)
+}
+
+// annotates dump-providers JSON and find-providers CSV with the location of each peer, saved as
+// `<file>.geo.csv` next to it
+pub async fn run(config: Config, paths: Vec<PathBuf>) -> anyhow::Result<()> {
+    let geoip = GeoIp::open(&config.databases)?;
+    for path in paths {
+        println!("* Locate peers in {}", path.display());
+        let mut csv_content = String::new();
+        let mut locations = BTreeMap::new();
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            // `<output dir>/find-providers/<cid>/<time>.csv` of finder ID, region, provider ID
+            let cid = path
+                .parent()
+                .and_then(Path::file_name)
+                .ok_or(anyhow::anyhow!("No CID in {}", path.display()))?
+                .to_string_lossy();
+            let address_book = address_book(&config, &cid).await?;
+            writeln!(&mut csv_content, "finder,region,provider,{LOCATION_HEADER}")?;
+            for line in tokio::fs::read_to_string(&path).await?.lines() {
+                let Some(provider) = line.splitn(3, ',').nth(2) else {
+                    println!("! Unexpected line {line}");
+                    continue;
+                };
+                let addrs = address_book.get(provider).cloned().unwrap_or_default();
+                let location = geoip.peer(&addrs)?;
+                writeln!(&mut csv_content, "{line},{}", location_fields(&location))?;
+                locations.insert(provider.to_string(), location);
+            }
+        } else {
+            writeln!(&mut csv_content, "peer id,addrs,{LOCATION_HEADER}")?;
+            for record in load_records(&path).await? {
+                let location = geoip.peer(&record.Addrs)?;
+                writeln!(
+                    &mut csv_content,
+                    "{},{},{}",
+                    record.ID,
+                    record.Addrs.join(" "),
+                    location_fields(&location)
+                )?;
+                locations.insert(record.ID, location);
+            }
+        }
+        summarize(locations.values());
+        save_csv(path.with_extension("geo.csv"), csv_content).await?
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use regex::Regex;
+
+use crate::{
+    config::Config,
+    geoip::{GeoIp, PeerLocation},
+};
+
+use super::{
+    location_fields, quote, save_csv, summarize, trace_dirs, trace_files, TraceFile,
+    LOCATION_HEADER,
+};
+
+// a provider record put to one of the closest peers, as `report_traces` of traces-provide.ipynb
+#[derive(Debug)]
+pub struct Record {
+    pub peer_id: String,
+    // `None` if no lookup response in the trace carries the peer
+    pub addrs: Option<Vec<String>>,
+    pub location: PeerLocation,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl Record {
+    pub const HEADER: &str = "trace,peer id,addrs,success,error";
+
+    pub fn collect(trace: &TraceFile, geoip: &GeoIp) -> anyhow::Result<Vec<Self>> {
+        let peer_addrs = peer_addrs(trace)?;
+        trace
+            .trace
+            .named("KademliaDHT.ProtocolMessenger.PutProvider")
+            .map(|span| {
+                let peer_id = span
+                    .str_attr("to")
+                    .ok_or(anyhow::anyhow!("No to attribute in PutProvider span"))?;
+                let error = span.error().map(Into::into);
+                let addrs = peer_addrs.get(peer_id).cloned();
+                Ok(Self {
+                    peer_id: peer_id.into(),
+                    location: geoip.peer(addrs.as_deref().unwrap_or_default())?,
+                    addrs,
+                    success: error.is_none(),
+                    error,
+                })
+            })
+            .collect()
+    }
+}
+
+// addresses of the peers in `GetClosestPeers` responses, whose `peers` attribute is a JSON array
+// of `{<peer id>: [<addr> <addr> ...]}` strings
+pub fn peer_addrs(trace: &TraceFile) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let peer = Regex::new(r"\{(\w*): \[([^\]]*)\]\}")?;
+    let mut peer_addrs = HashMap::new();
+    for span in trace
+        .trace
+        .named("KademliaDHT.ProtocolMessenger.GetClosestPeers")
+    {
+        let Some(span_peers) = span.str_attr("peers").filter(|peers| !peers.is_empty()) else {
+            continue;
+        };
+        for span_peer in serde_json::from_str::<Vec<String>>(span_peers)? {
+            let captures = peer
+                .captures(&span_peer)
+                .ok_or(anyhow::anyhow!("Unexpected peer {span_peer}"))?;
+            peer_addrs.insert(
+                captures[1].to_string(),
+                captures[2].split_whitespace().map(Into::into).collect(),
+            );
+        }
+    }
+    Ok(peer_addrs)
+}
+
+// the traces of provide unless `dirs` are given
+pub async fn run(config: Config, dirs: Vec<PathBuf>, csv: Option<PathBuf>) -> anyhow::Result<()> {
+    let dirs = trace_dirs(&config, dirs, &["provide/traces"])?;
+    let geoip = GeoIp::open(&config.databases)?;
+
+    let mut csv_content = String::new();
+    writeln!(&mut csv_content, "{},{LOCATION_HEADER}", Record::HEADER)?;
+    println!(
+        "{:<54} {:>7} {:>5}  {:<20} error",
+        "peer id", "success", "addrs", "country"
+    );
+    let mut locations = Vec::new();
+    let (mut total, mut succeeded) = (0, 0);
+    for dir in dirs {
+        for path in trace_files(&dir).await? {
+            let trace = TraceFile::load(&path).await?;
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let records = Record::collect(&trace, &geoip)?;
+            if records.is_empty() {
+                println!("! No PutProvider span ({})", path.display())
+            }
+            for record in records {
+                if record.addrs.is_none() {
+                    println!("! Peer addresses missing: {}", record.peer_id)
+                }
+                let addrs = record.addrs.unwrap_or_default();
+                let error = record.error.unwrap_or_default();
+                let country = (record.location.location.country.as_deref())
+                    .unwrap_or(record.location.class.name());
+                println!(
+                    "{:<54} {:>7} {:>5}  {country:<20} {error}",
+                    record.peer_id,
+                    record.success,
+                    addrs.len()
+                );
+                writeln!(
+                    &mut csv_content,
+                    "{name},{},{},{},{},{}",
+                    record.peer_id,
+                    addrs.join(" "),
+                    record.success,
+                    quote(&error),
+                    location_fields(&record.location)
+                )?;
+                locations.push(record.location);
+                total += 1;
+                succeeded += record.success as usize
+            }
+        }
+    }
+    println!("* Put {succeeded} of {total} provider record(s) successfully");
+    summarize(&locations);
+
+    if let Some(path) = csv {
+        save_csv(path, csv_content).await?
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use opentelemetry_proto::tonic::trace::v1::Span;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, traceparent::hex, traces};
+
+use super::{
+    trace_dirs, trace_files,
+    tree::{attribute, SpanRef, SpanTree},
+    TraceFile,
+};
+
+// span attributes that name the remote peer
+const PEER_KEYS: [&str; 3] = ["PeerId", "peer", "to"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkedBy {
+    // a span with the CID as `key`, e.g. `Dual.FindProvidersAsync`
+    Cid,
+    // a span on one of the providers found for the CID, e.g. a dial
+    Peer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedTrace {
+    pub trace_id: String,
+    pub by: LinkedBy,
+    pub spans: Vec<Span>,
+}
+
+// one logical retrieval: the trace of a `get` command, and the spans of the other traces that
+// serve it among those starting in its time range, saved as `<start time>.record.json` next to
+// the trace:
+//
+//     {
+//       "traceId": "<hex>",
+//       "cid": "<cid>",
+//       "providers": ["<peer id>", ...],
+//       "spans": [<OTLP JSON span>, ...],
+//       "linked": [{"traceId": "<hex>", "by": "cid" | "peer", "spans": [...]}, ...]
+//     }
+//
+// where `providers` are found by the lookups of the CID, and `linked` is in start time order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievalRecord {
+    pub trace_id: String,
+    pub cid: String,
+    pub providers: Vec<String>,
+    pub spans: Vec<Span>,
+    pub linked: Vec<LinkedTrace>,
+}
+
+impl RetrievalRecord {
+    // `None` if `trace` is not a retrieval
+    pub fn link(trace: &SpanTree, other: &SpanTree) -> Option<Self> {
+        let get_span = trace.named("CoreAPI.UnixfsAPI.Get").next()?;
+        let cid = get_span.str_attr("path")?.split('/').next_back()?;
+        let (start, end) = (
+            trace.spans().map(|span| span.start()).min()?,
+            trace.spans().map(|span| span.end()).max()?,
+        );
+        let mut other_traces = BTreeMap::<&[u8], Vec<SpanRef<'_>>>::new();
+        for span in other.spans() {
+            if (start..=end).contains(&span.start()) {
+                other_traces
+                    .entry(&span.span().trace_id)
+                    .or_default()
+                    .push(span)
+            }
+        }
+
+        let by_cid = other_traces
+            .iter()
+            .filter(|(_, spans)| spans.iter().any(|span| span.str_attr("key") == Some(cid)))
+            .map(|(trace_id, _)| *trace_id)
+            .collect::<BTreeSet<_>>();
+        let providers = by_cid
+            .iter()
+            .flat_map(|trace_id| &other_traces[trace_id])
+            .flat_map(|span| span.events())
+            .filter(|event| event.name == "found provider")
+            .filter_map(|event| attribute(&event.attributes, "id"))
+            .filter_map(|id| id.as_str().map(String::from))
+            .collect::<BTreeSet<_>>();
+        let on_provider = |span: &SpanRef<'_>| {
+            PEER_KEYS
+                .iter()
+                .filter_map(|key| span.str_attr(key))
+                .any(|peer| providers.contains(peer))
+        };
+
+        let mut linked = other_traces
+            .iter()
+            .filter_map(|(trace_id, spans)| {
+                let by = if by_cid.contains(trace_id) {
+                    LinkedBy::Cid
+                } else if spans.iter().any(on_provider) {
+                    LinkedBy::Peer
+                } else {
+                    return None;
+                };
+                Some(LinkedTrace {
+                    trace_id: hex(trace_id),
+                    by,
+                    spans: spans.iter().map(|span| span.span().clone()).collect(),
+                })
+            })
+            .collect::<Vec<_>>();
+        linked.sort_by_key(|trace| traces::range(&trace.spans).0);
+        Some(Self {
+            trace_id: hex(&get_span.span().trace_id),
+            cid: cid.into(),
+            providers: providers.into_iter().collect(),
+            spans: trace.spans().map(|span| span.span().clone()).collect(),
+            linked,
+        })
+    }
+
+    // `<start time>.record.json` of the trace file `<start time>.json`
+    pub fn path(trace_path: &Path) -> PathBuf {
+        trace_path.with_extension("record.json")
+    }
+
+    pub async fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        serde_json::from_slice(&tokio::fs::read(path).await?)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let linked = |by| self.linked.iter().filter(|trace| trace.by == by).count();
+        println!(
+            "* Linked {} trace(s) by CID {} and {} by its {} provider(s)",
+            linked(LinkedBy::Cid),
+            self.cid,
+            linked(LinkedBy::Peer),
+            self.providers.len()
+        );
+        tokio::fs::write(path, serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    // the trace, and the linked spans as its other traces
+    pub fn trace_file(self, path: PathBuf) -> TraceFile {
+        TraceFile {
+            path,
+            trace: SpanTree::new(self.spans),
+            other: SpanTree::new(self.linked.into_iter().flat_map(|trace| trace.spans)),
+        }
+    }
+}
+
+// (re)links the saved retrieval traces in `dirs`, e.g. those saved before linking or by the
+// former retrieve-traces.py
+pub async fn run(config: Config, dirs: Vec<PathBuf>) -> anyhow::Result<()> {
+    let mut count = 0;
+    for dir in trace_dirs(&config, dirs, &["get-once/traces", "get-hot/traces"])? {
+        for path in trace_files(&dir).await? {
+            let trace = TraceFile::load(&path).await?;
+            let Some(record) = RetrievalRecord::link(&trace.trace, &trace.other) else {
+                println!("! Not a retrieval trace ({})", path.display());
+                continue;
+            };
+            record.save(RetrievalRecord::path(&path)).await?;
+            count += 1
+        }
+    }
+    println!("* Saved {count} retrieval record(s)");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::{
+        common::v1::{any_value, AnyValue, KeyValue},
+        trace::v1::span::Event,
+    };
+
+    use super::*;
+
+    const CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+    const PROVIDER: &str = "12D3KooWProvider";
+
+    fn string(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.into(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.into())),
+            }),
+        }
+    }
+
+    fn span(trace: u8, name: &str, start: u64, attributes: Vec<KeyValue>) -> Span {
+        Span {
+            trace_id: vec![trace; 16],
+            span_id: vec![trace; 8],
+            name: name.into(),
+            start_time_unix_nano: start,
+            end_time_unix_nano: start + 10,
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn links_by_cid_and_provider() {
+        let trace = SpanTree::new([
+            span(
+                1,
+                "CoreAPI.UnixfsAPI.Get",
+                0,
+                vec![string("path", &format!("/ipfs/{CID}"))],
+            ),
+            span(1, "Bitswap.GetBlock", 90, vec![]),
+        ]);
+        let mut lookup = span(2, "Dual.FindProvidersAsync", 10, vec![string("key", CID)]);
+        lookup.events.push(Event {
+            name: "found provider".into(),
+            attributes: vec![string("id", PROVIDER)],
+            ..Default::default()
+        });
+        let other = SpanTree::new([
+            span(3, "swarm.Dial", 20, vec![string("PeerId", PROVIDER)]),
+            lookup,
+            span(4, "swarm.Dial", 30, vec![string("PeerId", "12D3KooWOther")]),
+            // after the retrieval
+            span(5, "Dual.FindProvidersAsync", 200, vec![string("key", CID)]),
+        ]);
+
+        let record = RetrievalRecord::link(&trace, &other).unwrap();
+        assert_eq!(record.trace_id, "01".repeat(16));
+        assert_eq!(record.cid, CID);
+        assert_eq!(record.providers, [PROVIDER]);
+        assert_eq!(record.spans.len(), 2);
+        assert_eq!(
+            record
+                .linked
+                .iter()
+                .map(|trace| (&*trace.trace_id, trace.by))
+                .collect::<Vec<_>>(),
+            [
+                (&*"02".repeat(16), LinkedBy::Cid),
+                (&*"03".repeat(16), LinkedBy::Peer)
+            ]
+        );
+
+        let json = serde_json::to_vec(&record).unwrap();
+        let trace_file = serde_json::from_slice::<RetrievalRecord>(&json)
+            .unwrap()
+            .trace_file(Default::default());
+        assert_eq!(trace_file.trace.len(), 2);
+        assert_eq!(trace_file.other.len(), 2);
+    }
+
+    #[test]
+    fn not_a_retrieval() {
+        let trace = SpanTree::new([span(1, "CoreAPI.RoutingAPI.Provide", 0, vec![])]);
+        assert!(RetrievalRecord::link(&trace, &SpanTree::default()).is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use super::{
+    seconds,
+    tree::{attributes, Query, SpanRef},
+    TraceFile,
+};
+
+fn print_span(span: &SpanRef<'_>, trace_start: u64) {
+    let indent = "  ".repeat(span.depth());
+    println!(
+        "{indent}{} +{:.3}s {:.3}s{}",
+        span.name(),
+        seconds(trace_start, span.start()),
+        seconds(span.start(), span.end()),
+        span.error()
+            .map(|error| format!(" ! {error}"))
+            .unwrap_or_default()
+    );
+    for (key, value) in span.attrs() {
+        println!("{indent}  {key} = {value}")
+    }
+    for event in span.events() {
+        print!(
+            "{indent}  @ +{:.3}s {}",
+            seconds(trace_start, event.time_unix_nano),
+            event.name
+        );
+        for (key, value) in attributes(&event.attributes) {
+            print!(" {key}={value}")
+        }
+        println!()
+    }
+}
+
+// spans of a trace file that match all filters, each with its subtree if `tree`
+pub async fn run(path: PathBuf, query: Query, other: bool, tree: bool) -> anyhow::Result<()> {
+    let trace = TraceFile::load(&path).await?;
+    let spans = if other { &trace.other } else { &trace.trace };
+    let trace_start = spans
+        .spans()
+        .map(|span| span.start())
+        .min()
+        .unwrap_or_default();
+    let mut count = 0;
+    for span in spans.find(&query) {
+        print_span(&span, trace_start);
+        if tree {
+            for descendant in span.descendants() {
+                print_span(&descendant, trace_start)
+            }
+        }
+        count += 1
+    }
+    println!("* {count} of {} span(s) matched", spans.len());
+    Ok(())
+}
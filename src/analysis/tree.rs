@@ -0,0 +1,532 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use opentelemetry_proto::tonic::{
+    common::v1::{any_value, AnyValue, KeyValue},
+    trace::v1::{span::Event, status::StatusCode, Span},
+};
+
+// an OTLP attribute value of any type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    Empty,
+}
+
+impl From<&AnyValue> for Value {
+    fn from(value: &AnyValue) -> Self {
+        match &value.value {
+            Some(any_value::Value::StringValue(value)) => Self::String(value.clone()),
+            Some(any_value::Value::BoolValue(value)) => Self::Bool(*value),
+            Some(any_value::Value::IntValue(value)) => Self::Int(*value),
+            Some(any_value::Value::DoubleValue(value)) => Self::Double(*value),
+            Some(any_value::Value::BytesValue(value)) => Self::Bytes(value.clone()),
+            Some(any_value::Value::ArrayValue(array)) => {
+                Self::Array(array.values.iter().map(Into::into).collect())
+            }
+            Some(any_value::Value::KvlistValue(list)) => Self::Map(
+                list.values
+                    .iter()
+                    .map(|entry| (entry.key.clone(), Self::from(entry.value.as_ref())))
+                    .collect(),
+            ),
+            None => Self::Empty,
+        }
+    }
+}
+
+impl From<Option<&AnyValue>> for Value {
+    fn from(value: Option<&AnyValue>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Empty)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => write!(f, "{value}"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Double(value) => write!(f, "{value}"),
+            Self::Bytes(value) => write!(f, "{}", crate::traceparent::hex(value)),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?
+                    }
+                    write!(f, "{value}")?
+                }
+                write!(f, "]")
+            }
+            Self::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?
+                    }
+                    write!(f, "{key}: {value}")?
+                }
+                write!(f, "}}")
+            }
+            Self::Empty => Ok(()),
+        }
+    }
+}
+
+pub fn attribute(attributes: &[KeyValue], key: &str) -> Option<Value> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.as_ref().into())
+}
+
+pub fn attributes(attributes: &[KeyValue]) -> Vec<(String, Value)> {
+    attributes
+        .iter()
+        .map(|attribute| (attribute.key.clone(), attribute.value.as_ref().into()))
+        .collect()
+}
+
+// spans of one or more traces, linked by `parentSpanId`
+#[derive(Debug, Default)]
+pub struct SpanTree {
+    spans: Vec<Span>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+impl SpanTree {
+    pub fn new(spans: impl IntoIterator<Item = Span>) -> Self {
+        let mut spans = spans.into_iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| span.start_time_unix_nano);
+        let index = spans
+            .iter()
+            .enumerate()
+            .map(|(i, span)| ((&span.trace_id[..], &span.span_id[..]), i))
+            .collect::<HashMap<_, _>>();
+        // a parent outside the saved spans makes a root as well
+        let mut parents = spans
+            .iter()
+            .map(|span| {
+                index
+                    .get(&(&span.trace_id[..], &span.parent_span_id[..]))
+                    .copied()
+            })
+            .collect::<Vec<_>>();
+        // malformed or duplicated exports may link spans in a cycle, cut where it closes so
+        // that every walk up or down the tree ends
+        for i in 0..parents.len() {
+            let mut visited = HashSet::from([i]);
+            let mut current = i;
+            while let Some(parent) = parents[current] {
+                if !visited.insert(parent) {
+                    parents[current] = None;
+                    break;
+                }
+                current = parent
+            }
+        }
+        let mut children = vec![Vec::new(); spans.len()];
+        for (i, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children[*parent].push(i)
+            }
+        }
+        Self {
+            spans,
+            parents,
+            children,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    fn node(&self, index: usize) -> SpanRef<'_> {
+        SpanRef { tree: self, index }
+    }
+
+    // in start time order
+    pub fn spans(&self) -> impl Iterator<Item = SpanRef<'_>> {
+        (0..self.spans.len()).map(|index| self.node(index))
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = SpanRef<'_>> {
+        self.spans().filter(|span| span.parent().is_none())
+    }
+
+    pub fn named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = SpanRef<'a>> {
+        self.spans().filter(move |span| span.name() == name)
+    }
+
+    pub fn find<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = SpanRef<'a>> {
+        self.spans().filter(move |span| query.matches(span))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpanRef<'a> {
+    tree: &'a SpanTree,
+    index: usize,
+}
+
+impl<'a> SpanRef<'a> {
+    pub fn span(&self) -> &'a Span {
+        &self.tree.spans[self.index]
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.span().name
+    }
+
+    pub fn start(&self) -> u64 {
+        self.span().start_time_unix_nano
+    }
+
+    pub fn end(&self) -> u64 {
+        self.span().end_time_unix_nano
+    }
+
+    pub fn attr(&self, key: &str) -> Option<Value> {
+        attribute(&self.span().attributes, key)
+    }
+
+    pub fn str_attr(&self, key: &str) -> Option<&'a str> {
+        let attribute = self
+            .span()
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == key)?;
+        match attribute.value.as_ref()?.value.as_ref()? {
+            any_value::Value::StringValue(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn attrs(&self) -> Vec<(String, Value)> {
+        attributes(&self.span().attributes)
+    }
+
+    pub fn events(&self) -> &'a [Event] {
+        &self.span().events
+    }
+
+    // the first event of the name
+    pub fn event(&self, name: &str) -> Option<&'a Event> {
+        self.events().iter().find(|event| event.name == name)
+    }
+
+    // status message if the span ended with an error
+    pub fn error(&self) -> Option<&'a str> {
+        self.span()
+            .status
+            .as_ref()
+            .filter(|status| status.code == StatusCode::Error as i32)
+            .map(|status| &*status.message)
+    }
+
+    pub fn parent(&self) -> Option<SpanRef<'a>> {
+        self.tree.parents[self.index].map(|index| self.tree.node(index))
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SpanRef<'a>> {
+        let tree = self.tree;
+        tree.children[self.index]
+            .iter()
+            .map(move |index| tree.node(*index))
+    }
+
+    // from the parent up to the root
+    pub fn ancestors(&self) -> impl Iterator<Item = SpanRef<'a>> {
+        std::iter::successors(self.parent(), SpanRef::parent)
+    }
+
+    // depth first, in start time order among siblings
+    pub fn descendants(&self) -> Vec<SpanRef<'a>> {
+        let mut descendants = Vec::new();
+        let mut stack = self.children().collect::<Vec<_>>();
+        stack.reverse();
+        while let Some(span) = stack.pop() {
+            let mut children = span.children().collect::<Vec<_>>();
+            children.reverse();
+            stack.extend(children);
+            descendants.push(span)
+        }
+        descendants
+    }
+
+    pub fn depth(&self) -> usize {
+        self.ancestors().count()
+    }
+}
+
+impl PartialEq for SpanRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.tree, other.tree) && self.index == other.index
+    }
+}
+
+// conjunction of span filters
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    name: Option<String>,
+    attrs: Vec<(String, Value)>,
+    // spans starting in the range
+    range: Option<(u64, u64)>,
+    ancestor: Option<Box<Query>>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn attr(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.attrs.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn range(mut self, start: u64, end: u64) -> Self {
+        self.range = Some((start, end));
+        self
+    }
+
+    // some ancestor matches `query`
+    pub fn ancestor(mut self, query: Query) -> Self {
+        self.ancestor = Some(Box::new(query));
+        self
+    }
+
+    pub fn matches(&self, span: &SpanRef<'_>) -> bool {
+        self.name.as_ref().is_none_or(|name| span.name() == name)
+            && self
+                .attrs
+                .iter()
+                .all(|(key, value)| span.attr(key).as_ref() == Some(value))
+            && self
+                .range
+                .is_none_or(|(start, end)| (start..=end).contains(&span.start()))
+            && self
+                .ancestor
+                .as_ref()
+                .is_none_or(|query| span.ancestors().any(|ancestor| query.matches(&ancestor)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::common::v1::{ArrayValue, KeyValueList};
+
+    use super::*;
+
+    fn span(trace: u8, id: u8, parent: Option<u8>, name: &str, start: u64) -> Span {
+        Span {
+            trace_id: vec![trace; 16],
+            span_id: vec![id; 8],
+            parent_span_id: parent.map(|parent| vec![parent; 8]).unwrap_or_default(),
+            name: name.into(),
+            start_time_unix_nano: start,
+            end_time_unix_nano: start + 10,
+            ..Default::default()
+        }
+    }
+
+    fn any(value: any_value::Value) -> Option<AnyValue> {
+        Some(AnyValue { value: Some(value) })
+    }
+
+    fn attr(mut span: Span, key: &str, value: any_value::Value) -> Span {
+        span.attributes.push(KeyValue {
+            key: key.into(),
+            value: any(value),
+        });
+        span
+    }
+
+    fn names<'a>(spans: impl IntoIterator<Item = SpanRef<'a>>) -> Vec<&'a str> {
+        spans.into_iter().map(|span| span.name()).collect()
+    }
+
+    fn tree() -> SpanTree {
+        SpanTree::new([
+            span(1, 3, Some(2), "leaf", 30),
+            span(1, 1, None, "root", 10),
+            span(1, 2, Some(1), "inner", 20),
+            span(1, 4, Some(1), "sibling", 40),
+            // parent not saved
+            span(1, 5, Some(9), "orphan", 50),
+            // same span ID in another trace
+            span(2, 2, Some(1), "other", 60),
+        ])
+    }
+
+    #[test]
+    fn links_parents() {
+        let tree = tree();
+        assert_eq!(tree.len(), 6);
+        assert_eq!(
+            names(tree.spans()),
+            ["root", "inner", "leaf", "sibling", "orphan", "other"]
+        );
+        assert_eq!(names(tree.roots()), ["root", "orphan", "other"]);
+        let root = tree.named("root").next().unwrap();
+        assert_eq!(names(root.children()), ["inner", "sibling"]);
+        assert_eq!(names(root.descendants()), ["inner", "leaf", "sibling"]);
+        let leaf = tree.named("leaf").next().unwrap();
+        assert_eq!(names(leaf.ancestors()), ["inner", "root"]);
+        assert_eq!(leaf.depth(), 2);
+        assert_eq!(leaf.parent().unwrap().parent(), Some(root));
+    }
+
+    #[test]
+    fn cuts_cycles() {
+        let tree = SpanTree::new([
+            span(1, 1, Some(3), "a", 10),
+            span(1, 2, Some(1), "b", 20),
+            span(1, 3, Some(2), "c", 30),
+            span(1, 4, Some(4), "self", 40),
+        ]);
+        assert_eq!(tree.roots().count(), 2);
+        for span in tree.spans() {
+            assert!(span.depth() < tree.len());
+            assert!(span.descendants().len() < tree.len());
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let tree = SpanTree::new([
+            attr(
+                span(1, 1, None, "get", 10),
+                "path",
+                any_value::Value::StringValue("/ipfs/cid".into()),
+            ),
+            attr(
+                span(1, 2, Some(1), "find", 20),
+                "key",
+                any_value::Value::StringValue("cid".into()),
+            ),
+            attr(
+                span(2, 3, None, "find", 30),
+                "key",
+                any_value::Value::StringValue("cid".into()),
+            ),
+            attr(
+                span(2, 4, Some(3), "dial", 40),
+                "count",
+                any_value::Value::IntValue(3),
+            ),
+        ]);
+        let find = |query: Query| {
+            tree.find(&query)
+                .map(|span| span.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(find(Query::new().name("find")), ["find", "find"]);
+        assert_eq!(find(Query::new().attr("key", "cid")), ["find", "find"]);
+        assert_eq!(find(Query::new().attr("key", "other")), [] as [&str; 0]);
+        assert_eq!(find(Query::new().attr("count", Value::Int(3))), ["dial"]);
+        assert_eq!(find(Query::new().range(15, 35)), ["find", "find"]);
+        assert_eq!(
+            find(Query::new().name("find").ancestor(Query::new().name("get"))),
+            ["find"]
+        );
+        assert_eq!(
+            find(Query::new().ancestor(Query::new().attr("key", "cid"))),
+            ["dial"]
+        );
+    }
+
+    #[test]
+    fn decodes_attributes() {
+        let span = [
+            ("string", any_value::Value::StringValue("s".into())),
+            ("bool", any_value::Value::BoolValue(true)),
+            ("int", any_value::Value::IntValue(-1)),
+            ("double", any_value::Value::DoubleValue(0.5)),
+            ("bytes", any_value::Value::BytesValue(vec![0xab, 0x01])),
+            (
+                "array",
+                any_value::Value::ArrayValue(ArrayValue {
+                    values: vec![
+                        any(any_value::Value::IntValue(1)).unwrap(),
+                        any(any_value::Value::StringValue("two".into())).unwrap(),
+                    ],
+                }),
+            ),
+            (
+                "map",
+                any_value::Value::KvlistValue(KeyValueList {
+                    values: vec![KeyValue {
+                        key: "k".into(),
+                        value: None,
+                    }],
+                }),
+            ),
+        ]
+        .into_iter()
+        .fold(span(1, 1, None, "span", 0), |span, (key, value)| {
+            attr(span, key, value)
+        });
+        let tree = SpanTree::new([span]);
+        let span = tree.spans().next().unwrap();
+        assert_eq!(span.str_attr("string"), Some("s"));
+        assert_eq!(span.str_attr("int"), None);
+        assert_eq!(span.attr("bool"), Some(Value::Bool(true)));
+        assert_eq!(span.attr("int").and_then(|value| value.as_i64()), Some(-1));
+        assert_eq!(span.attr("double"), Some(Value::Double(0.5)));
+        assert_eq!(span.attr("bytes").unwrap().to_string(), "ab01");
+        assert_eq!(
+            span.attr("array"),
+            Some(Value::Array(vec![Value::Int(1), "two".into()]))
+        );
+        assert_eq!(span.attr("array").unwrap().to_string(), "[1 two]");
+        assert_eq!(
+            span.attr("map"),
+            Some(Value::Map(vec![("k".into(), Value::Empty)]))
+        );
+        assert_eq!(span.attr("missing"), None);
+        assert_eq!(span.attrs().len(), 7);
+    }
+}
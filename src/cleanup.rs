@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Mutex,
+    },
+};
+
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::{Host, Output, Session};
+
+// commands undoing what the run did on its hosts, e.g. stopping daemons and removing repos
+// process-wide, so that an interrupted run can still undo everything before exiting
+static PENDING: Mutex<BTreeMap<u64, (Host, String)>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+// a pending cleanup, which is run on `reap` unless it is run or dismissed before
+// dropping the guard keeps the cleanup pending
+#[derive(Debug)]
+#[must_use]
+pub struct Guard {
+    id: u64,
+}
+
+pub fn register(host: &Host, command: impl Into<String>) -> Guard {
+    let id = NEXT_ID.fetch_add(1, SeqCst);
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.insert(id, (host.clone(), command.into()));
+    }
+    Guard { id }
+}
+
+fn take(id: u64) -> Option<(Host, String)> {
+    PENDING.lock().ok()?.remove(&id)
+}
+
+impl Guard {
+    // no longer pending once started, so that `reap` does not run it a second time concurrently
+    pub async fn run(self) -> anyhow::Result<()> {
+        let Some((host, command)) = take(self.id) else {
+            return Ok(());
+        };
+        host.run(command).await
+    }
+
+    // the undone work has been undone in some other way
+    pub fn dismiss(self) {
+        take(self.id);
+    }
+}
+
+// runs every pending cleanup, latest registered first, reporting instead of failing on errors
+pub async fn reap() {
+    while let Some((_, (host, command))) = PENDING
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.pop_last())
+    {
+        println!("* Clean up `{command}` on {}", host.name());
+        if let Err(err) = host.output(&command).await.and_then(Output::check) {
+            println!("! {err}")
+        }
+    }
+}
+
+// resolves on the first SIGINT (e.g. Ctrl-C) or SIGTERM with the signal name
+pub async fn interrupted() -> anyhow::Result<&'static str> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            Ok("SIGINT")
+        }
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
+
+// the repo of the daemons that experiments start without `IPFS_PATH`
+const DEFAULT_REPO: &str = "\"${IPFS_PATH:-$HOME/.ipfs}\"";
+// in each repo this run uses, holding `owner`
+const OWNER_FILE: &str = "izuko.owner";
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .unwrap_or_default()
+        .trim()
+        .into()
+}
+
+// `<controller hostname>:<pid>` of this run
+pub fn owner() -> String {
+    format!("{}:{}", hostname(), std::process::id())
+}
+
+// whether the run of `owner` may still be going, which cannot be told for other controllers
+fn alive(owner: &str) -> bool {
+    let Some((owner_hostname, pid)) = owner.rsplit_once(':') else {
+        return false;
+    };
+    owner_hostname != hostname() || std::path::Path::new(&format!("/proc/{pid}")).exists()
+}
+
+// command marking `repo` as used by this run, see `leftovers`
+pub fn mark(repo: &str) -> String {
+    format!("echo {} > {repo}/{OWNER_FILE}", owner())
+}
+
+// starts `daemon`, an `ipfs daemon` invocation on the default repo of `host`, marked as used by
+// this run; the cleanup runs `shutdown` and removes the mark
+pub fn spawn_daemon(host: &Host, daemon: &str, shutdown: &str) -> anyhow::Result<(Session, Guard)> {
+    let session = host.spawn(format!("{}; {daemon}", mark(DEFAULT_REPO)))?;
+    let cleanup = register(
+        host,
+        format!("{shutdown}; rm -f {DEFAULT_REPO}/{OWNER_FILE}"),
+    );
+    Ok((session, cleanup))
+}
+
+#[derive(Debug, Clone)]
+pub struct Leftover {
+    // `None` for a running daemon of the default repo
+    pub repo: Option<String>,
+    pub owner: Option<String>,
+}
+
+impl Leftover {
+    // owned by a run that may still be going, which must be left alone
+    pub fn in_use(&self) -> bool {
+        self.owner.as_deref().is_some_and(alive)
+    }
+
+    pub fn name(&self) -> &str {
+        self.repo.as_deref().unwrap_or("default repo daemon")
+    }
+
+    fn reap_command(&self) -> String {
+        match &self.repo {
+            Some(repo) => format!("IPFS_PATH={repo} ipfs shutdown; rm -rf {repo}"),
+            None => format!("ipfs shutdown; rm -f {DEFAULT_REPO}/{OWNER_FILE}"),
+        }
+    }
+}
+
+// scratch repos of ephemeral peers on `host` (see `EphemeralPeer::repo`), and the daemon of the
+// default repo if a run marked it; those of this run are not started yet when called on startup,
+// a default repo daemon without mark is started by hand and is not listed
+pub async fn leftovers(host: &Host) -> anyhow::Result<Vec<Leftover>> {
+    let output = host
+        .output(format!(
+            "for repo in /tmp/ipfs-*; do [ -d $repo ] && echo $repo $(cat $repo/{OWNER_FILE} 2>/dev/null); done; \
+             ipfs stats bw > /dev/null 2>&1 && echo default $(cat {DEFAULT_REPO}/{OWNER_FILE} 2>/dev/null)"
+        ))
+        .await?;
+    Ok(output
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let (repo, owner) = match line.trim().split_once(' ') {
+                Some((repo, owner)) => (repo, Some(owner.trim().to_string())),
+                None => (line.trim(), None),
+            };
+            match repo {
+                "default" => owner.is_some().then_some(Leftover { repo: None, owner }),
+                "" => None,
+                repo => Some(Leftover {
+                    repo: Some(repo.into()),
+                    owner,
+                }),
+            }
+        })
+        .collect())
+}
+
+pub async fn reap_leftovers(host: &Host, leftovers: &[Leftover]) -> anyhow::Result<()> {
+    for leftover in leftovers {
+        host.run(leftover.reap_command()).await?;
+    }
+    Ok(())
+}
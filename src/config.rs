@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{delegated, Host};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Retrieval {
+    // `ipfs dag get`
+    Dag,
+    // `ipfs get -o /dev/null`
+    Get,
+    // deserialized response of the peer's HTTP gateway
+    Gateway,
+    // `?format=raw` response of the peer's HTTP gateway
+    GatewayRaw,
+    // `?format=car` response of the peer's HTTP gateway
+    GatewayCar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Collector {
+    // Jaeger all-in-one container on the host, see `init-instance --telemetry`
+    Jaeger,
+    // OTLP receiver in izuko, reverse forwarded to the OTLP ports of the host
+    Embedded,
+}
+
+// every field is optional, each experiment falls back to its own default
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub host: Option<String>,
+    pub canary_host: Option<String>,
+    // label -> CID
+    pub cids: BTreeMap<String, String>,
+    // a label in `cids`, or a CID
+    pub cid: Option<String>,
+    pub retrieval: Option<Retrieval>,
+    pub concurrency: Option<usize>,
+    pub sessions: Option<usize>,
+    pub output_dir: Option<PathBuf>,
+    pub collector: Option<Collector>,
+    // HTTP Delegated Routing V1 endpoint to find providers with besides the DHT
+    pub delegated_routing: Option<String>,
+    pub timeouts: Timeouts,
+    pub databases: Databases,
+}
+
+// in seconds
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Timeouts {
+    pub find: Option<u64>,
+    // of each `ipfs swarm connect`
+    pub connect: Option<u64>,
+    pub download: Option<u64>,
+    pub ready: Option<u64>,
+}
+
+// MaxMind-format databases for locating peer addresses
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Databases {
+    pub city: Option<PathBuf>,
+    pub asn: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct Overrides {
+    /// Experiment file, ignored if missing and not given explicitly
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Host running the IPFS peers, `local` for this machine
+    #[arg(long, global = true)]
+    pub host: Option<String>,
+    /// Host providing fresh data for `get-once`
+    #[arg(long, global = true)]
+    pub canary_host: Option<String>,
+    /// CID label in the experiment file, or a CID
+    #[arg(long, global = true)]
+    pub cid: Option<String>,
+    /// How sessions download the CID
+    #[arg(long, global = true)]
+    pub retrieval: Option<Retrieval>,
+    /// Maximum number of concurrent sessions
+    #[arg(long, global = true)]
+    pub concurrency: Option<usize>,
+    /// Number of sessions, for `find-providers` and `ipns`
+    #[arg(long, global = true)]
+    pub sessions: Option<usize>,
+    /// Directory results are saved under [default: saved]
+    #[arg(long, global = true)]
+    pub output_dir: Option<PathBuf>,
+    /// Where kubo exports traces to [default: jaeger]
+    #[arg(long, global = true)]
+    pub collector: Option<Collector>,
+    /// Delegated routing endpoint to also find providers with, e.g. https://delegated-ipfs.dev
+    #[arg(long, global = true)]
+    pub delegated_routing: Option<String>,
+    /// Seconds to find providers or peers for
+    #[arg(long, global = true)]
+    pub find_timeout: Option<u64>,
+    /// Seconds each `ipfs swarm connect` may take
+    #[arg(long, global = true)]
+    pub connect_timeout: Option<u64>,
+    /// Seconds a download may take
+    #[arg(long, global = true)]
+    pub download_timeout: Option<u64>,
+    /// Seconds an ephemeral daemon has to become ready
+    #[arg(long, global = true)]
+    pub ready_timeout: Option<u64>,
+    /// GeoIP city database [default: GeoLite2-City.mmdb]
+    #[arg(long, global = true)]
+    pub city_database: Option<PathBuf>,
+    /// GeoIP ASN database [default: GeoLite2-ASN.mmdb]
+    #[arg(long, global = true)]
+    pub asn_database: Option<PathBuf>,
+}
+
+impl Config {
+    pub const DEFAULT_PATH: &'static str = "izuko.toml";
+
+    pub fn load(overrides: &Overrides) -> anyhow::Result<Self> {
+        let path = overrides
+            .config
+            .clone()
+            .unwrap_or(Self::DEFAULT_PATH.into());
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(content) => match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => serde_json::from_str(&content)?,
+                _ => toml::from_str(&content)?,
+            },
+            Err(err)
+                if err.kind() == std::io::ErrorKind::NotFound && overrides.config.is_none() =>
+            {
+                Self::default()
+            }
+            Err(err) => anyhow::bail!("{}: {err}", path.display()),
+        };
+        let Overrides {
+            config: _,
+            host,
+            canary_host,
+            cid,
+            retrieval,
+            concurrency,
+            sessions,
+            output_dir,
+            collector,
+            delegated_routing,
+            find_timeout,
+            connect_timeout,
+            download_timeout,
+            ready_timeout,
+            city_database,
+            asn_database,
+        } = overrides.clone();
+        config.host = host.or(config.host);
+        config.canary_host = canary_host.or(config.canary_host);
+        config.cid = cid.or(config.cid);
+        config.retrieval = retrieval.or(config.retrieval);
+        config.concurrency = concurrency.or(config.concurrency);
+        config.sessions = sessions.or(config.sessions);
+        config.output_dir = output_dir.or(config.output_dir);
+        config.collector = collector.or(config.collector);
+        config.delegated_routing = delegated_routing.or(config.delegated_routing);
+        config.timeouts.find = find_timeout.or(config.timeouts.find);
+        config.timeouts.connect = connect_timeout.or(config.timeouts.connect);
+        config.timeouts.download = download_timeout.or(config.timeouts.download);
+        config.timeouts.ready = ready_timeout.or(config.timeouts.ready);
+        config.databases.city = city_database.or(config.databases.city);
+        config.databases.asn = asn_database.or(config.databases.asn);
+        Ok(config)
+    }
+
+    pub fn host(&self) -> anyhow::Result<Host> {
+        let host = self.host.as_ref().ok_or(anyhow::anyhow!(
+            "no host, set `host` in {} or pass --host",
+            Self::DEFAULT_PATH
+        ))?;
+        Ok(host.parse()?)
+    }
+
+    pub fn canary_host(&self) -> anyhow::Result<Host> {
+        let host = self.canary_host.as_ref().ok_or(anyhow::anyhow!(
+            "no canary host, set `canary-host` in {} or pass --canary-host",
+            Self::DEFAULT_PATH
+        ))?;
+        Ok(host.parse()?)
+    }
+
+    // `default` is a label or a CID as well
+    pub fn cid<'a>(&'a self, default: &'a str) -> &'a str {
+        let cid = self.cid.as_deref().unwrap_or(default);
+        self.cids.get(cid).map(String::as_str).unwrap_or(cid)
+    }
+
+    pub fn retrieval(&self) -> Retrieval {
+        self.retrieval.unwrap_or(Retrieval::Dag)
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(10)
+    }
+
+    pub fn collector(&self) -> Collector {
+        self.collector.unwrap_or(Collector::Jaeger)
+    }
+
+    pub fn delegated_routing(&self) -> Option<delegated::Client> {
+        let endpoint = self.delegated_routing.as_ref()?;
+        Some(delegated::Client::new(endpoint).timeout(self.timeouts.find(100)))
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        self.output_dir.clone().unwrap_or("saved".into())
+    }
+}
+
+impl Timeouts {
+    pub fn find(&self, default: u64) -> Duration {
+        Duration::from_secs(self.find.unwrap_or(default))
+    }
+
+    pub fn connect(&self, default: u64) -> Duration {
+        Duration::from_secs(self.connect.unwrap_or(default))
+    }
+
+    pub fn download(&self, default: u64) -> Duration {
+        Duration::from_secs(self.download.unwrap_or(default))
+    }
+
+    pub fn ready(&self, default: u64) -> Duration {
+        Duration::from_secs(self.ready.unwrap_or(default))
+    }
+}
+
+impl Databases {
+    pub fn city(&self) -> PathBuf {
+        self.city.clone().unwrap_or("GeoLite2-City.mmdb".into())
+    }
+
+    pub fn asn(&self) -> PathBuf {
+        self.asn.clone().unwrap_or("GeoLite2-ASN.mmdb".into())
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use reqwest::{header::ACCEPT, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::routing::{null_as_default, FindProvsResponse};
+
+// wire format of `/routing/v1` records, the `peer` schema and the legacy `bitswap` schema that
+// some routers still return both carry an ID and addresses
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub Schema: String,
+    #[serde(default)]
+    pub ID: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub Addrs: Vec<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub Protocols: Vec<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Providers {
+    #[serde(default, deserialize_with = "null_as_default")]
+    Providers: Vec<Record>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Peers {
+    #[serde(default, deserialize_with = "null_as_default")]
+    Peers: Vec<Record>,
+}
+
+impl From<FindProvsResponse> for Record {
+    fn from(response: FindProvsResponse) -> Self {
+        Self {
+            Schema: "peer".into(),
+            ID: response.ID,
+            Addrs: response.Addrs,
+            Protocols: Vec::new(),
+        }
+    }
+}
+
+fn responses(records: Vec<Record>) -> impl Iterator<Item = FindProvsResponse> {
+    records
+        .into_iter()
+        .filter(|record| matches!(&*record.Schema, "peer" | "bitswap") && !record.ID.is_empty())
+        .map(|record| FindProvsResponse {
+            Addrs: record.Addrs,
+            ID: record.ID,
+        })
+}
+
+// client of the HTTP Delegated Routing V1 API, e.g. `https://delegated-ipfs.dev`
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    endpoint: String,
+    timeout: Option<Duration>,
+}
+
+impl Client {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.into().trim_end_matches('/').into(),
+            timeout: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    // `None` if not found
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<Option<T>> {
+        let mut request = self
+            .http
+            .get(format!("{}/routing/v1/{path}", self.endpoint))
+            .header(ACCEPT, "application/json");
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout)
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let status = response.status();
+        anyhow::ensure!(
+            status.is_success(),
+            "{status}: {}",
+            response.text().await?.trim()
+        );
+        Ok(Some(response.json().await?))
+    }
+
+    // the same shape as the providers of kubo `routing/findprovs`
+    pub async fn providers(&self, cid: &str) -> anyhow::Result<Vec<FindProvsResponse>> {
+        let providers = self
+            .get::<Providers>(&format!("providers/{cid}"))
+            .await?
+            .unwrap_or_default();
+        Ok(responses(providers.Providers).collect())
+    }
+
+    // the same shape as the final peer of kubo `routing/findpeer`
+    pub async fn peer(&self, peer_id: &str) -> anyhow::Result<Option<FindProvsResponse>> {
+        let peers = self
+            .get::<Peers>(&format!("peers/{peer_id}"))
+            .await?
+            .unwrap_or_default();
+        Ok(responses(peers.Peers).find(|response| response.ID == peer_id))
+    }
+}
+
+// a delegated router serving canned records, e.g. for running experiments against loopback:
+// `axum::serve(listener, Stub::default().provider(cid, record).router())`
+pub mod stub {
+    use std::{collections::HashMap, sync::Arc};
+
+    use axum::{
+        extract::{Path, State},
+        http::{header::CONTENT_TYPE, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+
+    use crate::routing::FindProvsResponse;
+
+    use super::{Peers, Providers, Record};
+
+    #[derive(Debug, Clone, Default)]
+    pub struct Stub {
+        providers: HashMap<String, Vec<FindProvsResponse>>,
+        peers: HashMap<String, FindProvsResponse>,
+    }
+
+    impl Stub {
+        // also answers the peer of the record
+        pub fn provider(mut self, cid: impl Into<String>, record: FindProvsResponse) -> Self {
+            self.providers
+                .entry(cid.into())
+                .or_default()
+                .push(record.clone());
+            self.peer(record)
+        }
+
+        pub fn peer(mut self, record: FindProvsResponse) -> Self {
+            self.peers.insert(record.ID.clone(), record);
+            self
+        }
+
+        pub fn router(self) -> Router {
+            Router::new()
+                .route("/routing/v1/providers/{cid}", get(providers))
+                .route("/routing/v1/peers/{peer_id}", get(peers))
+                .with_state(Arc::new(self))
+        }
+    }
+
+    fn json(body: &impl serde::Serialize) -> impl IntoResponse {
+        match serde_json::to_vec(body) {
+            Ok(body) => (StatusCode::OK, [(CONTENT_TYPE, "application/json")], body),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(CONTENT_TYPE, "text/plain")],
+                err.to_string().into_bytes(),
+            ),
+        }
+    }
+
+    async fn providers(
+        State(stub): State<Arc<Stub>>,
+        Path(cid): Path<String>,
+    ) -> impl IntoResponse {
+        let Some(records) = stub.providers.get(&cid) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        json(&Providers {
+            Providers: records.iter().cloned().map(Record::from).collect(),
+        })
+        .into_response()
+    }
+
+    async fn peers(
+        State(stub): State<Arc<Stub>>,
+        Path(peer_id): Path<String>,
+    ) -> impl IntoResponse {
+        let Some(record) = stub.peers.get(&peer_id) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        json(&Peers {
+            Peers: vec![record.clone().into()],
+        })
+        .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::{stub::Stub, *};
+
+    const CID: &str = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+
+    fn record(id: &str) -> FindProvsResponse {
+        FindProvsResponse {
+            ID: id.into(),
+            Addrs: vec!["/ip4/1.2.3.4/tcp/4001".into()],
+        }
+    }
+
+    #[tokio::test]
+    async fn client_against_stub() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = Stub::default()
+            .provider(CID, record("12D3KooWA"))
+            .provider(CID, record("12D3KooWB"))
+            .router();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let client = Client::new(format!("http://{addr}/")).timeout(Duration::from_secs(10));
+
+        let providers = client.providers(CID).await.unwrap();
+        assert_eq!(
+            providers
+                .iter()
+                .map(|provider| &*provider.ID)
+                .collect::<Vec<_>>(),
+            ["12D3KooWA", "12D3KooWB"]
+        );
+        assert_eq!(providers[0].Addrs, record("12D3KooWA").Addrs);
+        // not found
+        assert!(client.providers("bafkunknown").await.unwrap().is_empty());
+        let peer = client.peer("12D3KooWB").await.unwrap().unwrap();
+        assert_eq!(peer.ID, "12D3KooWB");
+        assert!(client.peer("12D3KooWC").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn bitswap_schema() {
+        let providers = serde_json::from_str::<Providers>(
+            r#"{"Providers": [
+                {"Schema": "bitswap", "Protocol": "transport-bitswap", "ID": "12D3KooWA", "Addrs": ["/ip4/1.2.3.4/tcp/4001"]},
+                {"Schema": "peer", "ID": "12D3KooWB", "Addrs": null, "Protocols": ["transport-bitswap"]},
+                {"Schema": "unknown"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            responses(providers.Providers)
+                .map(|response| response.ID)
+                .collect::<Vec<_>>(),
+            ["12D3KooWA", "12D3KooWB"]
+        );
+    }
+}
@@ -1,103 +1,56 @@
 use std::{
     fmt::Write,
     path::Path,
-    process::Stdio,
     sync::{Arc, Mutex},
-    time::{Duration, UNIX_EPOCH},
+    time::UNIX_EPOCH,
 };
 
-use serde::{Deserialize, Serialize};
+use crate::{
+    cleanup,
+    config::Config,
+    kubo::Client,
+    ready::Probe,
+    routing::{self, FindProvsResponse, Source},
+};
 use tokio::{
     fs::{create_dir_all, write},
-    process::Command,
-    spawn,
     sync::Semaphore,
     task::JoinSet,
-    time::{sleep, Instant},
+    time::Instant,
 };
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> anyhow::Result<()> {
-    let ipfs_host = "ec2-54-233-234-50.sa-east-1.compute.amazonaws.com";
-
-    // ipfs sigcomm'22
-    // let cid = "bafybeiftyvcar3vh7zua3xakxkb2h5ppo4giu5f3rkpsqgcfh7n7axxnsa";
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let ipfs_host = config.host()?;
     // hello world DAG
-    let cid = "baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea";
-    // hello
-    // let cid = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
-    // hello (blake3), appears no provider
-    // let cid = "bafkr4ihkr4ld3m4gqkjf4reryxsy2s5tkbxprqkow6fin2iiyvreuzzab4";
-    // apollo
-    // let cid = "QmSnuWmxptJZdLJpKRarxBMS2Ju2oANVrgbr2xWbie9b2D";
+    let cid = config.cid("baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea");
 
     println!("* Start IPFS daemon");
-    let daemon_session = spawn(
-        Command::new("ssh")
-            .arg(ipfs_host)
-            .arg("ipfs daemon")
-            .stdout(Stdio::null())
-            .status(),
-    );
+    let (daemon_session, daemon_cleanup) =
+        cleanup::spawn_daemon(&ipfs_host, "ipfs daemon", "ipfs shutdown")?;
+    let client =
+        Client::new(format!("http://{}:5001", ipfs_host.name())).timeout(config.timeouts.find(100));
     println!("* Wait for IPFS daemon up");
-    while {
-        sleep(Duration::from_millis(1000)).await;
-        let status = Command::new("ssh")
-            .arg(ipfs_host)
-            .arg("ipfs stats bw")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .await?;
-        !status.success()
-    } {}
+    let ready_after = Probe::default()
+        .deadline(config.timeouts.ready(60))
+        .wait(&ipfs_host, str::to_string, Some(&daemon_session))
+        .await?;
+    println!("* IPFS daemon up after {ready_after:?}");
 
     let result = async {
-        #[allow(non_snake_case, unused)]
-        #[derive(Deserialize, Debug)]
-        struct FindProvs {
-            Extra: String,
-            ID: String,
-            Responses: Option<Vec<FindProvsResponse>>,
-            Type: i32,
-        }
-
-        #[allow(non_snake_case)]
-        #[derive(Debug, Serialize, Deserialize)]
-        struct FindProvsResponse {
-            Addrs: Vec<String>,
-            ID: String,
-        }
-
         println!("* Find providers for {cid}");
-        let find_provs = reqwest::Client::new()
-            .post(format!("http://{ipfs_host}:5001/api/v0/routing/findprovs"))
-            .query(&[("arg", cid), ("num-providers", "10000")])
-            .timeout(Duration::from_secs(100))
-            .send()
+        let events = client
+            .routing_findprovs(cid, 10000)
             .await?
-            .error_for_status()?
-            .text()
+            .collect()
             .await?;
-        let find_provs_responses = find_provs
-            .lines()
-            .map(serde_json::from_str)
-            .collect::<Result<Vec<FindProvs>, _>>()?
-            .into_iter()
-            .filter_map(|find_provs| {
-                if find_provs.Type != 4 {
-                    return None;
-                }
-                find_provs.Responses
-            })
-            .flatten()
-            .collect::<Vec<_>>();
+        let find_provs_responses = routing::providers(&events).cloned().collect::<Vec<_>>();
         anyhow::ensure!(find_provs_responses.len() < 10000);
 
         // println!("{find_provs_responses:?}");
 
         let path = format!(
-            "saved/dump-providers/{cid}/{}",
+            "{}/dump-providers/{cid}/{}",
+            config.output_dir().display(),
             UNIX_EPOCH.elapsed()?.as_millis()
         );
         println!(
@@ -111,43 +64,70 @@ async fn main() -> anyhow::Result<()> {
             serde_json::to_vec_pretty(&find_provs_responses)?,
         )
         .await?;
+        write(
+            path.with_extension("events.json"),
+            serde_json::to_vec_pretty(&events)?,
+        )
+        .await?;
+
+        if let Some(delegated) = config.delegated_routing() {
+            println!(
+                "* Find providers for {cid} with delegated routing {}",
+                delegated.endpoint()
+            );
+            let mut records =
+                routing::tag(find_provs_responses.iter().cloned(), Source::Dht).collect::<Vec<_>>();
+            records.extend(routing::tag(
+                delegated.providers(cid).await?,
+                Source::Delegated,
+            ));
+            routing::print_sources(&records);
+            write(
+                path.with_extension("sources.json"),
+                serde_json::to_vec_pretty(&records)?,
+            )
+            .await?;
+        }
 
         let mut responses = Arc::new(Mutex::new(Vec::new()));
         let mut route_csv_content = Arc::new(Mutex::new(String::new()));
         let mut sessions = JoinSet::new();
-        let semaphore = Arc::new(Semaphore::new(10));
+        let semaphore = Arc::new(Semaphore::new(config.concurrency()));
         for response in find_provs_responses {
             let id = response.ID;
             let responses = responses.clone();
             let route_csv_content = route_csv_content.clone();
             let semaphore = semaphore.clone();
+            let ipfs_host = ipfs_host.clone();
+            let client = client.clone();
             sessions.spawn(async move {
                 let _permit = semaphore.acquire().await;
                 println!("* Find provider {id}");
                 let mut addrs = Vec::new();
                 for i in 0..3 {
                     let start = Instant::now();
-                    let output = Command::new("ssh")
-                        .arg(ipfs_host)
-                        .arg(format!("timeout -s SIGINT 100s ipfs routing findpeer {id}",))
-                        .output()
-                        .await?;
-                    if output.status.success() {
+                    let find_peer =
+                        async { client.routing_findpeer(&id).await?.collect().await }.await;
+                    if let Some(response) = find_peer
+                        .as_ref()
+                        .ok()
+                        .and_then(|events| routing::final_peer(events))
+                    {
                         let query_duration = start.elapsed();
                         writeln!(
                             &mut *route_csv_content
                                 .lock()
                                 .map_err(|err| anyhow::anyhow!("{err}"))?,
                             "{id},{},{}",
-                            ipfs_host.split('.').nth(1).unwrap_or("unknown"),
+                            ipfs_host.region(),
                             query_duration.as_secs_f32()
                         )?;
-                        addrs = String::from_utf8(output.stdout)?
-                            .lines()
-                            .map(|line| line.trim().into())
-                            .collect();
+                        addrs = response.Addrs.clone();
                         break;
-                    } else if output.status.code() != Some(1) {
+                    } else if find_peer.is_err_and(|err| {
+                        err.downcast_ref::<reqwest::Error>()
+                            .is_some_and(|err| err.is_timeout())
+                    }) {
                         println!("! Provider {id} not routable");
                         break;
                     }
@@ -194,13 +174,8 @@ async fn main() -> anyhow::Result<()> {
     }
 
     println!("* Shutdown IPFS daemon");
-    let status = Command::new("ssh")
-        .arg(ipfs_host)
-        .arg("ipfs shutdown")
-        .status()
-        .await?;
-    anyhow::ensure!(status.success());
-    daemon_session.await??;
+    daemon_cleanup.run().await?;
+    daemon_session.wait().await?;
 
     result
 }
@@ -0,0 +1,169 @@
+use std::{
+    fmt::Write,
+    iter::repeat_n,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    config::Config,
+    ports::PortAllocator,
+    ready::{NotReady, Probe},
+    EphemeralPeer, Host,
+};
+use tokio::{
+    fs::{create_dir_all, write},
+    task::JoinSet,
+};
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let config = Arc::new(config);
+    let ipfs_host = config.host()?;
+    // hello world DAG
+    let cid = config.cid("baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea");
+
+    let ports = PortAllocator::new(ipfs_host.clone());
+    let mut find_csv_content = Arc::new(Mutex::new(String::new()));
+    let mut sessions_csv_content = Arc::new(Mutex::new(format!(
+        "peer id,region,{},providers\n",
+        EphemeralPeer::READINESS_HEADER
+    )));
+    let mut sessions = JoinSet::new();
+    let mut responses = repeat_n((), config.sessions.unwrap_or(100));
+    for (index, ()) in responses.by_ref().take(config.concurrency()).enumerate() {
+        sessions.spawn(find_session(
+            index,
+            ipfs_host.clone(),
+            ports.clone(),
+            config.clone(),
+            cid.into(),
+            find_csv_content.clone(),
+            sessions_csv_content.clone(),
+        ));
+    }
+
+    // this part feels a little silly = =
+    let mut failed = false;
+    let mut overall_result = Ok(());
+    while let Some(result) = sessions.join_next().await {
+        let index = match result.map_err(Into::into).and_then(|result| result) {
+            Ok(index) => index,
+            Err(err) => {
+                println!("! {err}");
+                failed = true;
+                overall_result = Err(err);
+                continue;
+            }
+        };
+        if !failed && responses.next().is_some() {
+            sessions.spawn(find_session(
+                index,
+                ipfs_host.clone(),
+                ports.clone(),
+                config.clone(),
+                cid.into(),
+                find_csv_content.clone(),
+                sessions_csv_content.clone(),
+            ));
+        }
+    }
+    overall_result?;
+
+    let path = format!(
+        "{}/find-providers/{cid}/{}.csv",
+        config.output_dir().display(),
+        UNIX_EPOCH.elapsed()?.as_millis()
+    );
+    let path = Path::new(&path);
+    println!("* Save find provider results to {}", path.display());
+    create_dir_all(path.parent().unwrap()).await?;
+    let find_csv_content = Arc::get_mut(&mut find_csv_content)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    write(path, find_csv_content).await?;
+    let sessions_csv_content = Arc::get_mut(&mut sessions_csv_content)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    write(path.with_extension("sessions.csv"), sessions_csv_content).await?;
+    write(
+        path.with_extension("ports.json"),
+        serde_json::to_vec_pretty(&ports.history()?)?,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn find_session(
+    index: usize,
+    ipfs_host: Host,
+    ports: PortAllocator,
+    config: Arc<Config>,
+    cid: String,
+    find_csv_content: Arc<Mutex<String>>,
+    sessions_csv_content: Arc<Mutex<String>>,
+) -> anyhow::Result<usize> {
+    let lease = ports.allocate(index).await?;
+    println!(
+        "* [{index:02}] Initialize ephemeral IPFS peer on ports {:?}",
+        lease.ports()
+    );
+    let mut peer = EphemeralPeer::init(ipfs_host, index, lease, true).await?;
+
+    let result = async {
+        println!("* [{index:02}] Start IPFS daemon");
+        let probe = Probe::default().deadline(config.timeouts.ready(60));
+        match peer.start_with(&probe).await {
+            Ok(ready_after) => println!("* [{index:02}] IPFS daemon up after {ready_after:?}"),
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:02}] {err}");
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        }
+
+        let id = peer.id();
+        println!("* [{index:02}] Find providers from {id}");
+        let find_provs = peer
+            .output(format!(
+                "timeout -s SIGINT {}s ipfs routing findprovs {cid}",
+                config.timeouts.find(100).as_secs()
+            ))
+            .await?
+            .check()?;
+
+        let mut find_csv_content = find_csv_content
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        for line in find_provs.lines() {
+            writeln!(
+                &mut find_csv_content,
+                "{id},{},{}",
+                peer.host().region(),
+                line.trim()
+            )?
+        }
+        anyhow::Ok(Some(find_provs.lines().count()))
+    }
+    .await;
+
+    if let Ok(providers) = &result {
+        writeln!(
+            sessions_csv_content
+                .lock()
+                .map_err(|err| anyhow::anyhow!("{err}"))?,
+            "{},{},{},{}",
+            peer.id(),
+            peer.host().region(),
+            peer.readiness_fields(),
+            providers.map(|count| count.to_string()).unwrap_or_default()
+        )?
+    }
+    println!("* [{index:02}] Shutdown ephemeral IPFS peer");
+    peer.close().await?;
+    result?;
+
+    Ok(index)
+}
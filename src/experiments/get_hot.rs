@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::{
+    cleanup,
+    config::Config,
+    kubo::Client,
+    ready::{Condition, Probe},
+    traceparent::TraceParent,
+    traces::Traces,
+};
+use tokio::time::sleep;
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let ipfs_host = config.host()?;
+    let cid = config.cid("QmW8MwfuojKUT2VAVPFXtHa21jrvcAK5Sc3MYiWFo3RXFq");
+    let traces = Traces::start(config.collector(), &ipfs_host).await?;
+
+    println!("* Rotate identity");
+    ipfs_host
+        .run("ipfs key rm old; ipfs key rotate -o old")
+        .await?;
+
+    println!("* Disable reproviding");
+    ipfs_host
+        .run("ipfs config --json Experimental.StrategicProviding true")
+        .await?;
+
+    println!("* Start IPFS daemon");
+    let (daemon_session, daemon_cleanup) = cleanup::spawn_daemon(
+        &ipfs_host,
+        "OTEL_EXPORTER_OTLP_INSECURE=true OTEL_TRACES_EXPORTER=otlp ipfs daemon",
+        "ipfs repo gc; ipfs shutdown",
+    )?;
+    println!("* Wait for bootstrapping finish");
+    let ready_after = Probe::default()
+        .deadline(config.timeouts.ready(120))
+        .condition(Condition::RoutingTable(50))
+        .wait(&ipfs_host, str::to_string, Some(&daemon_session))
+        .await?;
+    println!("* Bootstrapped after {ready_after:?}");
+
+    let traceparent = TraceParent::random();
+    println!("* Download data {cid} in trace {}", traceparent.trace_id());
+    let result = Client::new(format!("http://{}:5001", ipfs_host.name()))
+        .timeout(config.timeouts.download(20))
+        .traceparent(traceparent)
+        .get(cid)
+        .await;
+    if let Err(err) = result {
+        println!("! Fail to download: {err}")
+    }
+
+    println!("* Clean downloaded blocks");
+    ipfs_host.run("ipfs repo gc").await?;
+
+    println!("* Wait IPFS to propagate trace");
+    sleep(Duration::from_secs(10)).await;
+
+    println!("* Retrieve trace");
+    let result = traces
+        .save_trace(
+            &traceparent.trace_id(),
+            config.output_dir().join("get-hot/traces"),
+        )
+        .await;
+    match result {
+        Ok(path) => println!("* Saved trace to {}", path.display()),
+        Err(err) => println!("! {err}"),
+    }
+
+    println!("* IPFS daemon shutdown");
+    daemon_cleanup.run().await?;
+    let status = daemon_session.wait().await?;
+    anyhow::ensure!(status.success());
+
+    traces.close().await?;
+
+    Ok(())
+}
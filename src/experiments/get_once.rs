@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::{
+    cleanup, config::Config, kubo::Client, ready::Probe, traceparent::TraceParent, traces::Traces,
+};
+use tokio::time::sleep;
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let ipfs_host = config.host()?;
+    let ipfs_canary_host = config.canary_host()?;
+    let traces = Traces::start(config.collector(), &ipfs_host).await?;
+
+    println!("* Rotate canary identity");
+    ipfs_canary_host
+        .run("ipfs key rm old; ipfs key rotate -o old")
+        .await?;
+
+    println!("* Start canary daemon");
+    let (daemon_session, daemon_cleanup) =
+        cleanup::spawn_daemon(&ipfs_canary_host, "ipfs daemon", "ipfs shutdown")?;
+    let ready_after = Probe::default()
+        .deadline(config.timeouts.ready(60))
+        .wait(&ipfs_canary_host, str::to_string, Some(&daemon_session))
+        .await?;
+    println!("* Canary daemon up after {ready_after:?}");
+
+    println!("* Generate random data and add to canary");
+    let output = ipfs_canary_host
+        .output("dd if=/dev/random bs=16M count=1 of=testdata && ipfs add testdata -Q")
+        .await?;
+    if !output.success() {
+        print!("{}", output.stderr);
+        anyhow::bail!("{}", output.status)
+    }
+    let cid = output.stdout.trim().to_string();
+    let data_cleanup = cleanup::register(
+        &ipfs_canary_host,
+        format!("ipfs pin rm {cid} && ipfs repo gc"),
+    );
+
+    println!("* Wait for providing data {cid}");
+    ipfs_canary_host
+        .run(format!("ipfs routing provide {cid}"))
+        .await?;
+
+    let traceparent = TraceParent::random();
+    println!("* Download data {cid} in trace {}", traceparent.trace_id());
+    let result = Client::new(format!("http://{}:5001", ipfs_host.name()))
+        .timeout(config.timeouts.download(100))
+        .traceparent(traceparent)
+        .get(&cid)
+        .await;
+    if let Err(err) = result {
+        println!("! Fail to download: {err}")
+    }
+
+    println!("* Clean downloaded blocks");
+    ipfs_host.run("ipfs repo gc").await?;
+
+    println!("* Canary daemon shutdown");
+    data_cleanup.run().await?;
+    daemon_cleanup.run().await?;
+    daemon_session.wait().await?;
+
+    println!("* Wait IPFS to propagate trace");
+    sleep(Duration::from_secs(10)).await;
+
+    println!("* Retrieve trace");
+    let path = traces
+        .save_trace(
+            &traceparent.trace_id(),
+            config.output_dir().join("get-once/traces"),
+        )
+        .await?;
+    println!("* Saved trace to {}", path.display());
+
+    traces.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::config::Config;
+use tokio::process::Command;
+
+// `telemetry` also starts a Jaeger all-in-one container as telemetry collector
+pub async fn run(config: Config, telemetry: bool) -> anyhow::Result<()> {
+    let host = config.host()?;
+    let ipfs_artifact = "../kubo/ipfs";
+
+    println!("* Sync IPFS artifact");
+    let status = Command::new("rsync")
+        .arg(ipfs_artifact)
+        .arg(format!("{}:", host.name()))
+        .status()
+        .await?;
+    anyhow::ensure!(status.success());
+
+    println!("* Install IPFS artifact and configure");
+    host.run(concat!(
+        "rm -rf .ipfs",
+        "; sudo cp ipfs /usr/local/bin",
+        // " && sleep 1",
+        " && ipfs init --profile server",
+        " && ipfs config Internal.Bitswap.ProviderSearchDelay 0",
+        " && ipfs config Addresses.API /ip4/0.0.0.0/tcp/5001",
+        " && sudo sysctl -w net.core.rmem_max=2500000",
+        " && sudo sysctl -w net.core.wmem_max=2500000",
+    ))
+    .await?;
+
+    if telemetry {
+        println!("* Start telemetry");
+        host.run(concat!(
+            "docker run -d --rm --name jaeger",
+            " -e COLLECTOR_OTLP_ENABLED=true",
+            " -e COLLECTOR_ZIPKIN_HOST_PORT=:9411",
+            " -p 5775:5775/udp",
+            " -p 6831:6831/udp",
+            " -p 6832:6832/udp",
+            " -p 5778:5778",
+            " -p 16685:16685",
+            " -p 16686:16686",
+            " -p 14250:14250",
+            " -p 14268:14268",
+            " -p 14269:14269",
+            " -p 4317:4317",
+            " -p 4318:4318",
+            " -p 9411:9411",
+            " jaegertracing/all-in-one"
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
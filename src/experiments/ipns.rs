@@ -0,0 +1,252 @@
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{
+    analysis::quote,
+    cleanup,
+    config::Config,
+    kubo::{HostClient, Timed},
+    ports::PortAllocator,
+    ready::{Condition, NotReady, Probe},
+    traceparent::TraceParent,
+    traces::Traces,
+    EphemeralPeer, Host,
+};
+use tokio::{
+    fs::{create_dir_all, write},
+    sync::Semaphore,
+    task::JoinSet,
+    time::sleep,
+};
+
+#[derive(Debug)]
+struct Resolve {
+    peer_id: String,
+    trace_id: String,
+    // of the RPC call, timed by curl on the peer's host
+    duration: Duration,
+    // the resolved path or the error
+    result: Result<String, String>,
+    // the published value
+    correct: bool,
+}
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let config = Arc::new(config);
+    let ipfs_host = config.host()?;
+    let ipfs_canary_host = config.canary_host()?;
+    let traces = Traces::start(config.collector(), &ipfs_host).await?;
+
+    println!("* Rotate canary identity");
+    ipfs_canary_host
+        .run("ipfs key rm old; ipfs key rotate -o old")
+        .await?;
+
+    println!("* Start canary daemon");
+    let (daemon_session, daemon_cleanup) =
+        cleanup::spawn_daemon(&ipfs_canary_host, "ipfs daemon", "ipfs shutdown")?;
+    let ready_after = Probe::default()
+        .deadline(config.timeouts.ready(60))
+        .wait(&ipfs_canary_host, str::to_string, Some(&daemon_session))
+        .await?;
+    println!("* Canary daemon up after {ready_after:?}");
+
+    println!("* Generate random data and add to canary");
+    let output = ipfs_canary_host
+        .output("dd if=/dev/random bs=1K count=1 of=testdata && ipfs add testdata -Q")
+        .await?;
+    if !output.success() {
+        print!("{}", output.stderr);
+        anyhow::bail!("{}", output.status)
+    }
+    let cid = output.stdout.trim().to_string();
+    let data_cleanup = cleanup::register(
+        &ipfs_canary_host,
+        format!("ipfs pin rm {cid} && ipfs repo gc"),
+    );
+    let value = format!("/ipfs/{cid}");
+    let ports = PortAllocator::new(ipfs_host.clone());
+
+    println!("* Publish {value} with canary identity");
+    // on the canary's default `Addresses.API`
+    let publish = HostClient::new(ipfs_canary_host.clone(), 5001)
+        .timeout(config.timeouts.find(100))
+        .name_publish(&value, "self")
+        .await;
+    let result = async {
+        let (name, publish_duration) = match publish {
+            Ok(Timed {
+                result: Ok(published),
+                duration,
+            }) => (published.Name, duration),
+            Ok(Timed {
+                result: Err(err), ..
+            })
+            | Err(err) => anyhow::bail!("Fail to publish: {err}"),
+        };
+        println!("* Published /ipns/{name} after {publish_duration:?}");
+
+        let semaphore = Arc::new(Semaphore::new(config.concurrency()));
+        let mut sessions = JoinSet::new();
+        for index in 0..config.sessions.unwrap_or(5) {
+            sessions.spawn(resolve_session(
+                index,
+                ipfs_host.clone(),
+                ports.clone(),
+                config.clone(),
+                name.clone(),
+                value.clone(),
+                semaphore.clone(),
+            ));
+        }
+        let mut resolves = Vec::new();
+        while let Some(result) = sessions.join_next().await {
+            match result.map_err(Into::into).and_then(|result| result) {
+                Ok(Some(resolve)) => resolves.push(resolve),
+                Ok(None) => {}
+                Err(err) => println!("! {err}"),
+            }
+        }
+        anyhow::Ok((name, publish_duration, resolves))
+    }
+    .await;
+
+    println!("* Canary daemon shutdown");
+    data_cleanup.run().await?;
+    daemon_cleanup.run().await?;
+    daemon_session.wait().await?;
+    let (name, publish_duration, resolves) = result?;
+
+    println!("* Wait IPFS to propagate trace");
+    sleep(Duration::from_secs(10)).await;
+
+    let mut csv_content = String::new();
+    writeln!(
+        &mut csv_content,
+        "name,publish,peer,region,resolve,success,correct,trace,error"
+    )?;
+    for resolve in &resolves {
+        println!("* Retrieve trace {}", resolve.trace_id);
+        match traces
+            .save_trace(&resolve.trace_id, config.output_dir().join("ipns/traces"))
+            .await
+        {
+            Ok(path) => println!("* Saved trace to {}", path.display()),
+            Err(err) => println!("! {err}"),
+        }
+        writeln!(
+            &mut csv_content,
+            "{name},{},{},{},{},{},{},{},{}",
+            publish_duration.as_secs_f32(),
+            resolve.peer_id,
+            ipfs_host.region(),
+            resolve.duration.as_secs_f32(),
+            resolve.result.is_ok(),
+            resolve.correct,
+            resolve.trace_id,
+            resolve
+                .result
+                .as_ref()
+                .err()
+                .map(|err| quote(err))
+                .unwrap_or_default()
+        )?;
+    }
+    let path = format!(
+        "{}/ipns/{}.csv",
+        config.output_dir().display(),
+        UNIX_EPOCH.elapsed()?.as_millis()
+    );
+    let path = Path::new(&path);
+    println!(
+        "* Save {} resolve metrics to {}",
+        resolves.len(),
+        path.display()
+    );
+    create_dir_all(path.parent().unwrap()).await?;
+    write(path, csv_content).await?;
+    write(
+        path.with_extension("ports.json"),
+        serde_json::to_vec_pretty(&ports.history()?)?,
+    )
+    .await?;
+
+    traces.close().await?;
+
+    Ok(())
+}
+
+// resolves `name` from a fresh peer once it has bootstrapped, `None` if it does not in time
+async fn resolve_session(
+    index: usize,
+    ipfs_host: Host,
+    ports: PortAllocator,
+    config: Arc<Config>,
+    name: String,
+    value: String,
+    semaphore: Arc<Semaphore>,
+) -> anyhow::Result<Option<Resolve>> {
+    let _permit = semaphore.acquire().await?;
+
+    let lease = ports.allocate(index).await?;
+    println!(
+        "* [{index:02}] Initialize ephemeral IPFS peer on ports {:?}",
+        lease.ports()
+    );
+    let mut peer = EphemeralPeer::init(ipfs_host, index, lease, true).await?;
+    peer.export_traces(true);
+
+    let result = async {
+        println!("* [{index:02}] Start IPFS daemon");
+        let probe = Probe::default()
+            .deadline(config.timeouts.ready(120))
+            .condition(Condition::RoutingTable(50));
+        match peer.start_with(&probe).await {
+            Ok(ready_after) => println!("* [{index:02}] Bootstrapped after {ready_after:?}"),
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:02}] {err}");
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        }
+
+        let traceparent = TraceParent::random();
+        println!(
+            "* [{index:02}] Resolve /ipns/{name} in trace {}",
+            traceparent.trace_id()
+        );
+        let Timed { result, duration } = peer
+            .host_client()
+            .timeout(config.timeouts.download(100))
+            .traceparent(traceparent)
+            .name_resolve(&format!("/ipns/{name}"), true)
+            .await?;
+        let result = result
+            .map(|resolved| resolved.Path)
+            .map_err(|err| err.to_string());
+        let correct = result.as_ref() == Ok(&value);
+        match &result {
+            Err(err) => println!("! [{index:02}] Fail to resolve: {err}"),
+            Ok(path) if !correct => {
+                println!("! [{index:02}] Resolved to {path} instead of {value}")
+            }
+            Ok(_) => println!("* [{index:02}] Resolved after {duration:?}"),
+        }
+        anyhow::Ok(Some(Resolve {
+            peer_id: peer.id().into(),
+            trace_id: traceparent.trace_id(),
+            duration,
+            result,
+            correct,
+        }))
+    }
+    .await;
+
+    println!("* [{index:02}] Shutdown ephemeral IPFS peer");
+    peer.close().await?;
+    result
+}
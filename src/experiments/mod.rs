@@ -0,0 +1,9 @@
+pub mod dump_providers;
+pub mod find_providers;
+pub mod get_hot;
+pub mod get_once;
+pub mod init_instance;
+pub mod ipns;
+pub mod profile_providers;
+pub mod provide;
+pub mod qoe;
@@ -0,0 +1,355 @@
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    config::{Config, Retrieval},
+    gateway,
+    ports::PortAllocator,
+    ready::{NotReady, Probe},
+    transport::{self, Attempt},
+    EphemeralPeer, Host,
+};
+use serde::Deserialize;
+use tokio::{
+    fs::{create_dir_all, read, read_dir, write},
+    task::JoinSet,
+    time::Instant,
+};
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let config = Arc::new(config);
+    let ipfs_host = config.host()?;
+    // hello world DAG
+    let cid = config.cid("baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea");
+
+    #[allow(non_snake_case)]
+    #[derive(Deserialize, Debug)]
+    struct FindProvsResponse {
+        Addrs: Vec<String>,
+        ID: String,
+    }
+
+    let mut path = None;
+    let mut read_dir = read_dir(config.output_dir().join("dump-providers").join(cid)).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let entry_path = Some(entry.path().with_extension("").with_extension(""));
+        path = path.max(entry_path)
+    }
+    let path = path.ok_or(anyhow::anyhow!("no dumped providers for {cid}"))?;
+    // status, time to first and last byte and size of gateway responses instead of download time
+    let (extension, columns) = if gateway::path(cid, config.retrieval()).is_some() {
+        ("gateway.csv", gateway::Response::HEADER)
+    } else {
+        ("download.csv", "seconds")
+    };
+    let mut download_csv_content =
+        Arc::new(Mutex::new(format!("peer id,region,route,{columns}\n")));
+    let mut sessions_csv_content = Arc::new(Mutex::new(format!(
+        "peer id,region,route,{},outcome\n",
+        EphemeralPeer::READINESS_HEADER
+    )));
+    // provider ID, whether by explicit routing, attempt
+    let mut connects = Arc::new(Mutex::new(Vec::<(String, bool, Attempt)>::new()));
+
+    let responses = serde_json::from_slice::<Vec<FindProvsResponse>>(
+        &read(path.with_extension("json")).await?,
+    )?;
+    println!(
+        "* Loaded {} provider records from {}",
+        responses.len(),
+        path.display()
+    );
+    let route_responses = serde_json::from_slice::<Vec<FindProvsResponse>>(
+        &read(path.with_extension("route.json")).await?,
+    )?;
+    println!(
+        "* Loaded {} provider records (explicit routing)",
+        responses.len()
+    );
+
+    let ports = PortAllocator::new(ipfs_host.clone());
+    let mut sessions = JoinSet::new();
+    let mut responses = responses
+        .into_iter()
+        .map(|find_provs| (find_provs, false))
+        .chain(
+            route_responses
+                .into_iter()
+                .map(|find_provs| (find_provs, true)),
+        );
+    for (index, (find_provs, route)) in responses.by_ref().take(config.concurrency()).enumerate() {
+        sessions.spawn(get_session(
+            index,
+            ipfs_host.clone(),
+            ports.clone(),
+            find_provs.ID,
+            find_provs.Addrs,
+            cid.into(),
+            config.clone(),
+            route,
+            download_csv_content.clone(),
+            sessions_csv_content.clone(),
+            connects.clone(),
+        ));
+    }
+
+    // this part feels a little silly = =
+    let mut failed = false;
+    let mut overall_result = Ok(());
+    while let Some(result) = sessions.join_next().await {
+        let index = match result.map_err(Into::into).and_then(|result| result) {
+            Ok(index) => index,
+            Err(err) => {
+                println!("! {err}");
+                failed = true;
+                overall_result = Err(err);
+                continue;
+            }
+        };
+        if !failed {
+            if let Some((find_provs, route)) = responses.next() {
+                sessions.spawn(get_session(
+                    index,
+                    ipfs_host.clone(),
+                    ports.clone(),
+                    find_provs.ID,
+                    find_provs.Addrs,
+                    cid.into(),
+                    config.clone(),
+                    route,
+                    download_csv_content.clone(),
+                    sessions_csv_content.clone(),
+                    connects.clone(),
+                ));
+            }
+        }
+    }
+    overall_result?;
+
+    let path = format!(
+        "{}/profile-providers/{cid}/{}.{extension}",
+        config.output_dir().display(),
+        UNIX_EPOCH.elapsed()?.as_millis()
+    );
+    let path = Path::new(&path);
+    println!("* Save download metrics to {}", path.display());
+    create_dir_all(path.parent().unwrap()).await?;
+    let download_csv_content = Arc::get_mut(&mut download_csv_content)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    write(path, download_csv_content).await?;
+    let sessions_csv_content = Arc::get_mut(&mut sessions_csv_content)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    write(
+        path.with_extension("").with_extension("sessions.csv"),
+        sessions_csv_content,
+    )
+    .await?;
+    let connects = Arc::get_mut(&mut connects)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    transport::summarize(connects.iter().map(|(_, _, attempt)| attempt));
+    let mut connect_csv_content = String::new();
+    writeln!(
+        &mut connect_csv_content,
+        "peer id,region,route,{}",
+        Attempt::HEADER
+    )?;
+    for (id, route, attempt) in connects.iter() {
+        writeln!(
+            &mut connect_csv_content,
+            "{id},{},{route},{}",
+            ipfs_host.region(),
+            attempt.fields()
+        )?;
+    }
+    write(
+        path.with_extension("").with_extension("connect.csv"),
+        connect_csv_content,
+    )
+    .await?;
+    write(
+        path.with_extension("").with_extension("ports.json"),
+        serde_json::to_vec_pretty(&ports.history()?)?,
+    )
+    .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_session(
+    index: usize,
+    ipfs_host: Host,
+    ports: PortAllocator,
+    id: String,
+    addrs: Vec<String>,
+    cid: String,
+    config: Arc<Config>,
+    route: bool,
+    download_csv_content: Arc<Mutex<String>>,
+    sessions_csv_content: Arc<Mutex<String>>,
+    connects: Arc<Mutex<Vec<(String, bool, Attempt)>>>,
+) -> anyhow::Result<usize> {
+    let addrs = addrs
+        .into_iter()
+        .filter(|addr| !transport::is_local(addr))
+        .map(|addr| format!("{addr}/p2p/{id}"))
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        println!("! [{index:02}] No available address to {id}");
+        writeln!(
+            sessions_csv_content
+                .lock()
+                .map_err(|err| anyhow::anyhow!("{err}"))?,
+            "{id},{},{route},,,no address",
+            ipfs_host.region()
+        )?;
+        return Ok(index);
+    }
+
+    let lease = ports.allocate(index).await?;
+    println!(
+        "* [{index:02}] Initialize ephemeral IPFS peer on ports {:?}",
+        lease.ports()
+    );
+    let mut peer = EphemeralPeer::init(ipfs_host, index, lease, false).await?;
+
+    let result = async {
+        println!("* [{index:02}] Start IPFS daemon for downloading from {id}");
+        let probe = Probe::default().deadline(config.timeouts.ready(60));
+        match peer.start_with(&probe).await {
+            Ok(ready_after) => println!("* [{index:02}] IPFS daemon up after {ready_after:?}"),
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:02}] {err}");
+                return Ok("not ready");
+            }
+            Err(err) => return Err(err),
+        }
+
+        println!("* [{index:02}] Connect provider peer by each transport");
+        let attempts = match transport::connect_each(
+            &mut peer,
+            &id,
+            &addrs,
+            config.timeouts.connect(30),
+            &probe,
+        )
+        .await
+        {
+            Ok(attempts) => attempts,
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:02}] {err}");
+                return Ok("not ready");
+            }
+            Err(err) => return Err(err),
+        };
+        for attempt in &attempts {
+            println!(
+                "{} [{index:02}] {} {} after {:?}",
+                if attempt.success { "*" } else { "!" },
+                attempt.class.name(),
+                match (attempt.success, attempt.preconnected, &attempt.remote) {
+                    (true, ..) => "connected".into(),
+                    (false, true, _) => "already connected".into(),
+                    (false, false, Some(remote)) => format!("connected by {remote}"),
+                    (false, false, None) => "failed".into(),
+                },
+                attempt.duration
+            )
+        }
+        connects
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .extend(
+                attempts
+                    .into_iter()
+                    .map(|attempt| (id.clone(), route, attempt)),
+            );
+
+        println!("* [{index:02}] Connect provider peer");
+        let status = peer
+            .status(format!("ipfs swarm connect {}", addrs.join(" ")))
+            .await?;
+        if !status.success() {
+            println!("! [{index:02}] All attempts to connect {id} failed");
+            return Ok("connect failed");
+        }
+
+        if let Some(path) = gateway::path(&cid, config.retrieval()) {
+            println!("* [{index:02}] Fetch {path} from peer gateway");
+            let response = gateway::fetch(&peer, &path, config.timeouts.download(30)).await?;
+            if !response.success() {
+                println!(
+                    "! [{index:02}] Gateway status {} for {cid} via {id}",
+                    response.status
+                )
+            }
+            let mut download_csv_content = download_csv_content
+                .lock()
+                .map_err(|err| anyhow::anyhow!("{err}"))?;
+            writeln!(
+                &mut download_csv_content,
+                "{id},{},{route},{}",
+                peer.host().region(),
+                response.fields()
+            )?;
+            return Ok(if response.success() {
+                "fetched"
+            } else {
+                "fetch failed"
+            });
+        }
+
+        println!("* [{index:02}] Download from peer");
+        let start = Instant::now();
+        let dag = config.retrieval() == Retrieval::Dag;
+        let status = peer
+            .status(format!(
+                "timeout -s SIGINT {}s ipfs {} {cid} {}",
+                config.timeouts.download(30).as_secs(),
+                if dag { "dag get" } else { "get -o /dev/null" },
+                if dag { " && echo" } else { "" }
+            ))
+            .await?;
+        if !status.success() {
+            println!("! [{index:02}] Failed to finish download via {id}");
+            return Ok("download failed");
+        }
+
+        let mut download_csv_content = download_csv_content
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        writeln!(
+            &mut download_csv_content,
+            "{id},{},{route},{}",
+            peer.host().region(),
+            start.elapsed().as_secs_f32()
+        )?;
+        anyhow::Ok("downloaded")
+    }
+    .await;
+
+    if let Ok(outcome) = &result {
+        writeln!(
+            sessions_csv_content
+                .lock()
+                .map_err(|err| anyhow::anyhow!("{err}"))?,
+            "{id},{},{route},{},{outcome}",
+            peer.host().region(),
+            peer.readiness_fields()
+        )?
+    }
+    println!("* [{index:02}] Shutdown ephemeral IPFS peer");
+    peer.close().await?;
+    result?;
+
+    Ok(index)
+}
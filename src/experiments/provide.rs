@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use crate::{cleanup, config::Config, kubo::Client, traceparent::TraceParent, traces::Traces};
+use tokio::time::sleep;
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let ipfs_host = config.host()?;
+    let traces = Traces::start(config.collector(), &ipfs_host).await?;
+
+    println!("* Generate random data and add to IPFS");
+    let output = ipfs_host
+        .output("dd if=/dev/random bs=16M count=1 of=testdata && ipfs add testdata -Q")
+        .await?;
+    if !output.success() {
+        print!("{}", output.stderr);
+        anyhow::bail!("{}", output.status)
+    }
+    let cid = output.stdout.trim().to_string();
+    let data_cleanup = cleanup::register(&ipfs_host, format!("ipfs pin rm {cid} && ipfs repo gc"));
+
+    println!("* Wait for providing data {cid}");
+    let traceparent = TraceParent::random();
+    println!("* Provide in trace {}", traceparent.trace_id());
+    Client::new(format!("http://{}:5001", ipfs_host.name()))
+        .traceparent(traceparent)
+        .routing_provide(&cid)
+        .await?
+        .collect()
+        .await?;
+
+    println!("* Clean up added data");
+    data_cleanup.run().await?;
+
+    println!("* Wait IPFS to propagate trace");
+    sleep(Duration::from_secs(10)).await;
+
+    println!("* Retrieve trace");
+    let path = traces
+        .save_trace(
+            &traceparent.trace_id(),
+            config.output_dir().join("provide/traces"),
+        )
+        .await?;
+    println!("* Saved trace to {}", path.display());
+
+    traces.close().await?;
+
+    Ok(())
+}
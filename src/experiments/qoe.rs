@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{
+    config::{Config, Retrieval},
+    gateway,
+    kubo::Client,
+    ports::PortAllocator,
+    ready::{NotReady, Probe},
+    routing::{self, FindProvsResponse, Source},
+    transport::{self, Attempt},
+    EphemeralPeer, Host,
+};
+use tokio::{
+    fs::{create_dir_all, write},
+    sync::Semaphore,
+    task::JoinSet,
+    time::Instant,
+};
+
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let config = Arc::new(config);
+    let ipfs_host = config.host()?;
+    // hello world DAG
+    let cid = config.cid("baguqeerasords4njcts6vs7qvdjfcvgnume4hqohf65zsfguprqphs3icwea");
+
+    println!("* Find providers for {cid}");
+    let events = Client::new(format!("http://{}:5001", ipfs_host.name()))
+        .timeout(config.timeouts.find(100))
+        .routing_findprovs(cid, 1000)
+        .await?
+        .collect()
+        .await?;
+    let find_provs_responses = routing::providers(&events).cloned().collect::<Vec<_>>();
+    anyhow::ensure!(find_provs_responses.len() < 1000);
+    let mut records = routing::tag(find_provs_responses.clone(), Source::Dht).collect::<Vec<_>>();
+    if let Some(delegated) = config.delegated_routing() {
+        println!(
+            "* Find providers for {cid} with delegated routing {}",
+            delegated.endpoint()
+        );
+        records.extend(routing::tag(
+            delegated.providers(cid).await?,
+            Source::Delegated,
+        ));
+        routing::print_sources(&records);
+    }
+    // each provider once, with the addresses of the first source that has any
+    let mut providers = BTreeMap::<String, (FindProvsResponse, Vec<Source>)>::new();
+    for record in records {
+        let (response, sources) = providers
+            .entry(record.response.ID.clone())
+            .or_insert((record.response.clone(), Vec::new()));
+        if response.Addrs.is_empty() {
+            response.Addrs = record.response.Addrs
+        }
+        sources.push(record.source)
+    }
+
+    ipfs_host
+        .output(format!("ipfs routing findprovs {cid}"))
+        .await?
+        .check()?;
+    // TODO
+
+    // println!("{:?}", find_provs_responses.collect::<Vec<_>>());
+
+    let ports = PortAllocator::new(ipfs_host.clone());
+    let mut sessions = JoinSet::new();
+    let semaphore = Arc::new(Semaphore::new(config.concurrency()));
+    let mut sessions_csv_content = Arc::new(Mutex::new(format!(
+        "peer id,region,{},outcome,query seconds,score\n",
+        EphemeralPeer::READINESS_HEADER
+    )));
+    let mut gateway_csv_content = Arc::new(Mutex::new(format!(
+        "peer id,region,{}\n",
+        gateway::Response::HEADER
+    )));
+    // provider ID, attempt
+    let mut connects = Arc::new(Mutex::new(Vec::<(String, Attempt)>::new()));
+    for (index, (find_provs, sources)) in providers.into_values().enumerate() {
+        println!(
+            "* [{index:04}] Spawn download session with peer id {} by {}",
+            find_provs.ID,
+            sources
+                .iter()
+                .map(Source::name)
+                .collect::<Vec<_>>()
+                .join(" and ")
+        );
+        sessions.spawn(get_session(
+            index,
+            ipfs_host.clone(),
+            ports.clone(),
+            find_provs.ID,
+            find_provs.Addrs,
+            cid.into(),
+            config.clone(),
+            semaphore.clone(),
+            sessions_csv_content.clone(),
+            gateway_csv_content.clone(),
+            connects.clone(),
+        ));
+    }
+
+    while let Some(result) = sessions.join_next().await {
+        let (id, score) = match result {
+            Ok(Ok((id, score))) => (id, score),
+            result => {
+                println!("! {result:?}");
+                continue;
+            }
+        };
+        println!("*** Provider {id} Score {score}")
+    }
+
+    let path = format!(
+        "{}/qoe/{cid}/{}.csv",
+        config.output_dir().display(),
+        UNIX_EPOCH.elapsed()?.as_millis()
+    );
+    let path = Path::new(&path);
+    println!("* Save session results to {}", path.display());
+    create_dir_all(path.parent().unwrap()).await?;
+    let sessions_csv_content = Arc::get_mut(&mut sessions_csv_content)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    write(path, sessions_csv_content).await?;
+
+    if gateway::path(cid, config.retrieval()).is_some() {
+        let gateway_csv_content = Arc::get_mut(&mut gateway_csv_content)
+            .ok_or(anyhow::anyhow!("unexpected reference"))?
+            .get_mut()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        write(path.with_extension("gateway.csv"), gateway_csv_content).await?;
+    }
+
+    let connects = Arc::get_mut(&mut connects)
+        .ok_or(anyhow::anyhow!("unexpected reference"))?
+        .get_mut()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    transport::summarize(connects.iter().map(|(_, attempt)| attempt));
+    let mut csv_content = String::new();
+    writeln!(&mut csv_content, "peer id,region,{}", Attempt::HEADER)?;
+    for (id, attempt) in connects.iter() {
+        writeln!(
+            &mut csv_content,
+            "{id},{},{}",
+            ipfs_host.region(),
+            attempt.fields()
+        )?;
+    }
+    let connect_path = path.with_extension("connect.csv");
+    println!(
+        "* Save {} connect attempts to {}",
+        connects.len(),
+        connect_path.display()
+    );
+    write(connect_path, csv_content).await?;
+    write(
+        path.with_extension("ports.json"),
+        serde_json::to_vec_pretty(&ports.history()?)?,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_session(
+    index: usize,
+    ipfs_host: Host,
+    ports: PortAllocator,
+    id: String,
+    mut addrs: Vec<String>,
+    cid: String,
+    config: Arc<Config>,
+    semaphore: Arc<Semaphore>,
+    sessions_csv_content: Arc<Mutex<String>>,
+    gateway_csv_content: Arc<Mutex<String>>,
+    connects: Arc<Mutex<Vec<(String, Attempt)>>>,
+) -> anyhow::Result<(String, f32)> {
+    let _permit = semaphore.acquire().await?;
+    let fallback_query = addrs.is_empty();
+
+    let lease = ports.allocate(index).await?;
+    println!(
+        "* [{index:04}] Initialize ephemeral IPFS peer on ports {:?}",
+        lease.ports()
+    );
+    let mut peer = EphemeralPeer::init(ipfs_host, index, lease, fallback_query).await?;
+
+    let probe = Probe::default().deadline(config.timeouts.ready(60));
+    // of the fallback through the local ssh, kept apart from the timings curl takes on the host
+    let mut query_duration = Duration::ZERO;
+    let result = async {
+        println!("* [{index:04}] Start IPFS daemon");
+        match peer.start_with(&probe).await {
+            Ok(ready_after) => println!("* [{index:04}] IPFS daemon up after {ready_after:?}"),
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:04}] {err}");
+                return Ok(("not ready", 0.));
+            }
+            Err(err) => return Err(err),
+        }
+
+        if fallback_query {
+            println!("> [{index:04}] No address in provider record, fallback to expicit routing");
+            let start = Instant::now();
+            let output = peer.output(format!("ipfs routing findpeer {id}")).await?;
+            if output.success() {
+                addrs = output
+                    .stdout
+                    .lines()
+                    .map(|line| line.trim().into())
+                    .collect();
+            }
+            query_duration = start.elapsed();
+
+            println!("> [{index:04}] Shutdown ephemeral IPFS peer");
+            peer.shutdown().await?;
+
+            if addrs.is_empty() {
+                println!("! [{index:04}] No available address to {id}");
+                return Ok(("no address", 0.));
+            }
+
+            println!("> [{index:04}] Rotate key and disable DHT");
+            peer.rotate_identity(false).await?;
+
+            println!("> [{index:04}] Restart IPFS daemon");
+            match peer.start_with(&probe).await {
+                Ok(ready_after) => println!("* [{index:04}] IPFS daemon up after {ready_after:?}"),
+                Err(err) if err.is::<NotReady>() => {
+                    println!("! [{index:04}] {err}");
+                    return Ok(("not ready", 0.));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let addrs = addrs
+            .iter()
+            .filter(|addr| !transport::is_local(addr))
+            .map(|addr| format!("{addr}/p2p/{id}"))
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            println!("! [{index:04}] No available address to {id}");
+            return Ok(("no address", 0.));
+        }
+
+        println!("* [{index:04}] Connect provider peer by each transport");
+        let attempts = match transport::connect_each(
+            &mut peer,
+            &id,
+            &addrs,
+            config.timeouts.connect(30),
+            &probe,
+        )
+        .await
+        {
+            Ok(attempts) => attempts,
+            Err(err) if err.is::<NotReady>() => {
+                println!("! [{index:04}] {err}");
+                return Ok(("not ready", 0.));
+            }
+            Err(err) => return Err(err),
+        };
+        for attempt in &attempts {
+            println!(
+                "{} [{index:04}] {} {} after {:?}",
+                if attempt.success { "*" } else { "!" },
+                attempt.class.name(),
+                match (attempt.success, attempt.preconnected, &attempt.remote) {
+                    (true, ..) => "connected".into(),
+                    (false, true, _) => "already connected".into(),
+                    (false, false, Some(remote)) => format!("connected by {remote}"),
+                    (false, false, None) => "failed".into(),
+                },
+                attempt.duration
+            )
+        }
+        connects
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .extend(attempts.into_iter().map(|attempt| (id.clone(), attempt)));
+
+        println!("* [{index:04}] Connect provider peer");
+        let status = peer
+            .status(format!("ipfs swarm connect {}", addrs.join(" ")))
+            .await?;
+        if !status.success() {
+            println!("! [{index:04}] All attempts to connect {id} failed");
+            return Ok(("connect failed", 0.));
+        }
+
+        if let Some(path) = gateway::path(&cid, config.retrieval()) {
+            println!("* [{index:04}] Fetch {path} from peer gateway");
+            let response = gateway::fetch(&peer, &path, config.timeouts.download(100)).await?;
+            println!(
+                "* [{index:04}] Gateway status {} first byte {:?} last byte {:?}",
+                response.status, response.first_byte, response.last_byte
+            );
+            writeln!(
+                gateway_csv_content
+                    .lock()
+                    .map_err(|err| anyhow::anyhow!("{err}"))?,
+                "{id},{},{}",
+                peer.host().region(),
+                response.fields()
+            )?;
+            if !response.success() {
+                println!("! [{index:04}] Failed to fetch {cid} via {id}");
+                return Ok(("fetch failed", 0.));
+            }
+            return Ok(("fetched", 1. / response.last_byte.as_secs_f32()));
+        }
+
+        println!("* [{index:04}] Download from peer");
+        let start = Instant::now();
+        let dag = config.retrieval() == Retrieval::Dag;
+        let status = peer
+            .status(format!(
+                "timeout -s SIGINT {}s ipfs {} {cid} {}",
+                config.timeouts.download(100).as_secs(),
+                if dag { "dag get" } else { "get -o /dev/null" },
+                if dag { " && echo" } else { "" }
+            ))
+            .await?;
+        if !status.success() {
+            println!("! [{index:04}] Failed to finish download {cid} via {id}");
+            return Ok(("download failed", 0.));
+        }
+        anyhow::Ok((
+            "downloaded",
+            1. / (start.elapsed() + query_duration).as_secs_f32(),
+        ))
+    }
+    .await;
+
+    if let Ok((outcome, score)) = &result {
+        writeln!(
+            sessions_csv_content
+                .lock()
+                .map_err(|err| anyhow::anyhow!("{err}"))?,
+            "{id},{},{},{outcome},{},{score}",
+            peer.host().region(),
+            peer.readiness_fields(),
+            query_duration.as_secs_f32()
+        )?
+    }
+    println!("* [{index:04}] Shutdown ephemeral IPFS peer");
+    peer.close().await?;
+
+    Ok((id, result?.1))
+}
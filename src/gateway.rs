@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use crate::{config::Retrieval, EphemeralPeer};
+
+// a response of the HTTP gateway of an ephemeral peer, as timed by curl on the peer's host
+#[derive(Debug, Clone)]
+pub struct Response {
+    // 0 if no response arrived in time
+    pub status: u16,
+    // time to first byte, from the start of the request
+    pub first_byte: Duration,
+    // time to last byte, i.e. of the whole request
+    pub last_byte: Duration,
+    pub size: u64,
+}
+
+impl Response {
+    pub const HEADER: &str = "status,first byte seconds,last byte seconds,size";
+
+    pub fn success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn fields(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.status,
+            self.first_byte.as_secs_f32(),
+            self.last_byte.as_secs_f32(),
+            self.size
+        )
+    }
+}
+
+// `/ipfs/<cid>` with the response format of `retrieval`, `None` if it is not through a gateway
+pub fn path(cid: &str, retrieval: Retrieval) -> Option<String> {
+    let format = match retrieval {
+        Retrieval::Gateway => "",
+        Retrieval::GatewayRaw => "?format=raw",
+        Retrieval::GatewayCar => "?format=car",
+        Retrieval::Dag | Retrieval::Get => return None,
+    };
+    Some(format!("/ipfs/{cid}{format}"))
+}
+
+// fetches `path` from the gateway of `peer`, whose body is discarded
+pub async fn fetch(
+    peer: &EphemeralPeer,
+    path: &str,
+    timeout: Duration,
+) -> anyhow::Result<Response> {
+    let output = peer
+        .host()
+        .output(format!(
+            "curl -s -o /dev/null --max-time {} -w '%{{http_code}} %{{time_starttransfer}} %{{time_total}} %{{size_download}}' 'http://127.0.0.1:{}{path}'",
+            timeout.as_secs(),
+            peer.ports().gateway
+        ))
+        .await?;
+    // curl writes out the timings even if the request fails, e.g. on timeout
+    let fields = output.stdout.split_whitespace().collect::<Vec<_>>();
+    let [status, first_byte, last_byte, size] = fields[..] else {
+        anyhow::bail!(
+            "unexpected curl output {:?}: {}",
+            output.stdout,
+            output.stderr.trim()
+        )
+    };
+    Ok(Response {
+        status: status.parse()?,
+        first_byte: Duration::from_secs_f64(first_byte.parse()?),
+        last_byte: Duration::from_secs_f64(last_byte.parse()?),
+        size: size.parse()?,
+    })
+}
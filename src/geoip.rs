@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
+
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+
+use crate::config::Databases;
+
+// what an address tells about where a peer is, in the order of preference among its addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddrClass {
+    // public IP, located unless missing in the databases
+    Public,
+    // `/p2p-circuit`, only the relay is reachable at the address
+    Relay,
+    // `/dns*`, not resolved to stay offline and reproducible
+    Dns,
+    // private, shared (CGNAT), link local or unique local IP
+    Private,
+    Loopback,
+    // not an IP or DNS address, e.g. `/onion3`
+    Other,
+    // no address known for the peer
+    Missing,
+}
+
+impl AddrClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Relay => "relay",
+            Self::Dns => "dns",
+            Self::Private => "private",
+            Self::Loopback => "loopback",
+            Self::Other => "other",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+pub fn classify(addr: &str) -> (AddrClass, Option<IpAddr>) {
+    if addr.contains("/p2p-circuit") {
+        return (AddrClass::Relay, None);
+    }
+    let mut protocols = addr.split('/').skip(1);
+    let ip = match (protocols.next(), protocols.next()) {
+        (Some("ip4"), Some(ip)) => ip.parse::<Ipv4Addr>().ok().map(IpAddr::V4),
+        (Some("ip6"), Some(ip)) => ip.parse::<Ipv6Addr>().ok().map(IpAddr::V6),
+        (Some(protocol), _) if protocol.starts_with("dns") => return (AddrClass::Dns, None),
+        _ => None,
+    };
+    let Some(ip) = ip else {
+        return (AddrClass::Other, None);
+    };
+    let class = if ip.is_loopback() {
+        AddrClass::Loopback
+    } else if match ip {
+        IpAddr::V4(ip) => {
+            ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                // 100.64.0.0/10
+                || ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64
+        }
+        IpAddr::V6(ip) => {
+            ip.is_unspecified()
+                // fc00::/7 and fe80::/10
+                || ip.segments()[0] & 0xfe00 == 0xfc00
+                || ip.segments()[0] & 0xffc0 == 0xfe80
+        }
+    } {
+        AddrClass::Private
+    } else {
+        AddrClass::Public
+    };
+    (class, Some(ip))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Location {
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub asn: Option<u32>,
+    pub org: Option<String>,
+}
+
+// where a peer is, judged from the most telling of its addresses
+#[derive(Debug, Clone)]
+pub struct PeerLocation {
+    pub class: AddrClass,
+    pub ip: Option<IpAddr>,
+    pub location: Location,
+}
+
+// local MaxMind-format databases, e.g. GeoLite2 City and ASN
+#[derive(Debug)]
+pub struct GeoIp {
+    city: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIp {
+    // a missing database leaves its fields empty
+    pub fn open(databases: &Databases) -> anyhow::Result<Self> {
+        fn open(path: &Path) -> anyhow::Result<Option<Reader<Vec<u8>>>> {
+            if !path.exists() {
+                println!("! No GeoIP database {}", path.display());
+                return Ok(None);
+            }
+            Reader::open_readfile(path)
+                .map(Some)
+                .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+        }
+        Ok(Self {
+            city: open(&databases.city())?,
+            asn: open(&databases.asn())?,
+        })
+    }
+
+    // `None` if the IP is in neither database
+    pub fn locate(&self, ip: IpAddr) -> anyhow::Result<Option<Location>> {
+        fn lookup<'a, T: serde::Deserialize<'a>>(
+            reader: &'a Option<Reader<Vec<u8>>>,
+            ip: IpAddr,
+        ) -> anyhow::Result<Option<T>> {
+            let Some(reader) = reader else {
+                return Ok(None);
+            };
+            match reader.lookup(ip) {
+                Ok(record) => Ok(Some(record)),
+                Err(MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        }
+        let english = |names: Option<BTreeMap<&str, &str>>| {
+            names.and_then(|names| names.get("en").map(|name| name.to_string()))
+        };
+        let city = lookup::<geoip2::City>(&self.city, ip)?;
+        let asn = lookup::<geoip2::Asn>(&self.asn, ip)?;
+        if city.is_none() && asn.is_none() {
+            return Ok(None);
+        }
+        let (country, city) = city
+            .map(|city| {
+                (
+                    english(city.country.and_then(|country| country.names)),
+                    english(city.city.and_then(|city| city.names)),
+                )
+            })
+            .unwrap_or_default();
+        Ok(Some(Location {
+            country,
+            city,
+            asn: asn.as_ref().and_then(|asn| asn.autonomous_system_number),
+            org: asn
+                .and_then(|asn| asn.autonomous_system_organization)
+                .map(Into::into),
+        }))
+    }
+
+    // the first located public address, or the class of the most telling one
+    pub fn peer(&self, addrs: &[String]) -> anyhow::Result<PeerLocation> {
+        let mut best = PeerLocation {
+            class: AddrClass::Missing,
+            ip: None,
+            location: Default::default(),
+        };
+        for addr in addrs {
+            let (class, ip) = classify(addr);
+            if let (AddrClass::Public, Some(ip)) = (class, ip) {
+                if let Some(location) = self.locate(ip)? {
+                    return Ok(PeerLocation {
+                        class,
+                        ip: Some(ip),
+                        location,
+                    });
+                }
+            }
+            if class < best.class {
+                best.class = class;
+                best.ip = ip
+            }
+        }
+        Ok(best)
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    convert::Infallible,
+    process::{ExitStatus, Stdio},
+    str::FromStr,
+};
+
+use tokio::{process::Command, sync::oneshot, task::JoinHandle};
+
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Ssh(String),
+    // run commands with `sh -c` on the controller, e.g. against a loopback kubo
+    Local,
+}
+
+#[derive(Debug, Clone)]
+pub struct Host {
+    transport: Transport,
+}
+
+impl Host {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            transport: Transport::Ssh(name.into()),
+        }
+    }
+
+    pub fn local() -> Self {
+        Self {
+            transport: Transport::Local,
+        }
+    }
+
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    pub fn is_local(&self) -> bool {
+        matches!(self.transport, Transport::Local)
+    }
+
+    // also the address that the host's network services (RPC API, Jaeger) are reached on
+    pub fn name(&self) -> &str {
+        match &self.transport {
+            Transport::Ssh(name) => name,
+            Transport::Local => "localhost",
+        }
+    }
+
+    // EC2 hostnames look like `ec2-<ip>.<region>.compute.amazonaws.com`
+    pub fn region(&self) -> &str {
+        match &self.transport {
+            Transport::Ssh(name) => name.split('.').nth(1).unwrap_or("unknown"),
+            Transport::Local => "local",
+        }
+    }
+
+    pub fn command(&self, command: &str) -> Command {
+        match &self.transport {
+            Transport::Ssh(name) => {
+                let mut ssh = Command::new("ssh");
+                ssh.arg(name).arg(command);
+                ssh
+            }
+            Transport::Local => {
+                let mut sh = Command::new("sh");
+                sh.arg("-c").arg(command);
+                sh
+            }
+        }
+    }
+
+    pub async fn status(&self, command: impl AsRef<str>) -> anyhow::Result<ExitStatus> {
+        Ok(self.command(command.as_ref()).status().await?)
+    }
+
+    pub async fn run(&self, command: impl AsRef<str>) -> anyhow::Result<()> {
+        let command = command.as_ref();
+        let status = self.status(command).await?;
+        anyhow::ensure!(status.success(), "`{command}` on {}: {status}", self.name());
+        Ok(())
+    }
+
+    pub async fn output(&self, command: impl AsRef<str>) -> anyhow::Result<Output> {
+        let output = self
+            .command(command.as_ref())
+            .stdin(Stdio::null())
+            // e.g. when a `ready::Probe` check times out
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        Ok(Output {
+            status: output.status,
+            stdout: String::from_utf8(output.stdout)?,
+            stderr: String::from_utf8(output.stderr)?,
+        })
+    }
+
+    pub fn spawn(&self, command: impl AsRef<str>) -> anyhow::Result<Session> {
+        Session::spawn(self.command(command.as_ref()))
+    }
+
+    // makes `local_port` on the controller reachable as `remote_port` on the host, as long as the
+    // session lives; nothing to do for a local host if both ports are the same
+    pub fn forward_remote(
+        &self,
+        remote_port: u16,
+        local_port: u16,
+    ) -> anyhow::Result<Option<Session>> {
+        match &self.transport {
+            Transport::Ssh(name) => {
+                let mut ssh = Command::new("ssh");
+                ssh.args(["-N", "-o", "ExitOnForwardFailure=yes", "-R"])
+                    .arg(format!("{remote_port}:127.0.0.1:{local_port}"))
+                    .arg(name)
+                    .kill_on_drop(true);
+                Ok(Some(Session::spawn(ssh)?))
+            }
+            Transport::Local => {
+                anyhow::ensure!(
+                    remote_port == local_port,
+                    "cannot forward port {remote_port} to {local_port} on local host"
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl FromStr for Host {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "local" | "localhost" => Self::local(),
+            name => Self::new(name),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Output {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    pub fn check(self) -> anyhow::Result<String> {
+        anyhow::ensure!(self.success(), "{}: {}", self.status, self.stderr.trim());
+        Ok(self.stdout)
+    }
+}
+
+#[derive(Debug)]
+pub struct Session {
+    wait: JoinHandle<std::io::Result<ExitStatus>>,
+    kill: oneshot::Sender<()>,
+}
+
+impl Session {
+    fn spawn(mut command: Command) -> anyhow::Result<Self> {
+        let mut child = command.stdout(Stdio::null()).spawn()?;
+        let (kill, killed) = oneshot::channel();
+        let wait = tokio::spawn(async move {
+            tokio::select! {
+                status = child.wait() => status,
+                Ok(()) = killed => {
+                    child.kill().await?;
+                    child.wait().await
+                }
+            }
+        });
+        Ok(Session { wait, kill })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.wait.is_finished()
+    }
+
+    pub async fn wait(self) -> anyhow::Result<ExitStatus> {
+        Ok(self.wait.await??)
+    }
+
+    // kills the local process, i.e. the ssh client for remote hosts
+    pub async fn kill(self) -> anyhow::Result<ExitStatus> {
+        let _ = self.kill.send(());
+        Ok(self.wait.await??)
+    }
+}
@@ -0,0 +1,249 @@
+use std::path::{Path, PathBuf};
+
+use opentelemetry_proto::tonic::trace::v1::{Span, TracesData};
+use tonic::{transport::Channel, Streaming};
+
+use crate::{traces, Host};
+
+// messages, client and server of `jaeger-idl/proto/api_v3/query_service.proto`, see `build.rs`
+// `SpansResponseChunk` of older revisions is wire compatible with the `TracesData` of newer ones
+pub mod api_v3 {
+    tonic::include_proto!("jaeger.api_v3");
+}
+
+pub use api_v3::{
+    query_service_client::QueryServiceClient,
+    query_service_server::{QueryService, QueryServiceServer},
+    FindTracesRequest, GetOperationsRequest, GetOperationsResponse, GetServicesRequest,
+    GetServicesResponse, GetTraceRequest, Operation, TraceQueryParameters,
+};
+
+// the service name Kubo reports its spans with
+pub const KUBO: &str = "Kubo";
+
+// client of Jaeger's `QueryService`, listening on port 16685 of the all-in-one image
+#[derive(Debug, Clone)]
+pub struct Client {
+    grpc: QueryServiceClient<Channel>,
+}
+
+impl Client {
+    // `endpoint` e.g. `http://host:16685`
+    pub async fn connect(endpoint: impl Into<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            // a busy trace easily exceeds the 4 MiB default
+            grpc: QueryServiceClient::connect(endpoint.into())
+                .await?
+                .max_decoding_message_size(100 << 20),
+        })
+    }
+
+    // the collector started by `init-instance` next to the IPFS daemon
+    pub async fn on(host: &Host) -> anyhow::Result<Self> {
+        Self::connect(format!("http://{}:16685", host.name())).await
+    }
+
+    pub async fn get_trace(
+        &self,
+        request: GetTraceRequest,
+    ) -> anyhow::Result<Streaming<TracesData>> {
+        Ok(self.grpc.clone().get_trace(request).await?.into_inner())
+    }
+
+    pub async fn find_traces(
+        &self,
+        query: TraceQueryParameters,
+    ) -> anyhow::Result<Streaming<TracesData>> {
+        let request = FindTracesRequest { query: Some(query) };
+        Ok(self.grpc.clone().find_traces(request).await?.into_inner())
+    }
+
+    pub async fn services(&self) -> anyhow::Result<Vec<String>> {
+        let request = GetServicesRequest {};
+        Ok(self
+            .grpc
+            .clone()
+            .get_services(request)
+            .await?
+            .into_inner()
+            .services)
+    }
+
+    pub async fn operations(&self, service: &str) -> anyhow::Result<Vec<Operation>> {
+        let request = GetOperationsRequest {
+            service: service.into(),
+            span_kind: Default::default(),
+        };
+        Ok(self
+            .grpc
+            .clone()
+            .get_operations(request)
+            .await?
+            .into_inner()
+            .operations)
+    }
+
+    // spans of the trace, which kubo reports a while after the traced request finishes
+    pub async fn trace(&self, trace_id: &str) -> anyhow::Result<Vec<Span>> {
+        let spans = spans(
+            self.get_trace(GetTraceRequest {
+                trace_id: trace_id.into(),
+                ..Default::default()
+            })
+            .await?,
+        )
+        .await?;
+        anyhow::ensure!(!spans.is_empty(), "empty trace {trace_id}");
+        Ok(spans)
+    }
+
+    // the trace, and the spans of other traces in its time range, see `traces::save`
+    pub async fn save_trace(
+        &self,
+        trace_id: &str,
+        dir: impl AsRef<Path>,
+    ) -> anyhow::Result<PathBuf> {
+        let trace_spans = self.trace(trace_id).await?;
+        let (start, end) = traces::range(&trace_spans);
+        let other_spans = spans(
+            self.find_traces(TraceQueryParameters {
+                service_name: KUBO.into(),
+                start_time_min: Some(traces::timestamp(start)),
+                start_time_max: Some(traces::timestamp(end)),
+                ..Default::default()
+            })
+            .await?,
+        )
+        .await?
+        .into_iter()
+        .filter(|span| span.trace_id != trace_spans[0].trace_id)
+        .collect::<Vec<_>>();
+        traces::save(trace_id, &trace_spans, &other_spans, dir).await
+    }
+}
+
+// all spans in a response stream
+pub async fn spans(mut chunks: Streaming<TracesData>) -> anyhow::Result<Vec<Span>> {
+    let mut spans = Vec::new();
+    while let Some(chunk) = chunks.message().await? {
+        spans.extend(traces::flatten(chunk.resource_spans))
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span, TracesData};
+    use prost_types::Timestamp;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{codegen::BoxStream, transport::Server, Request, Response, Status};
+
+    use super::*;
+
+    const TRACE_ID: [u8; 16] = [1; 16];
+
+    fn chunk(trace_id: [u8; 16], name: &str) -> TracesData {
+        TracesData {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: trace_id.into(),
+                        span_id: vec![2; 8],
+                        name: name.into(),
+                        start_time_unix_nano: 1_000,
+                        end_time_unix_nano: 2_000,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    struct Fake;
+
+    fn stream(chunks: Vec<TracesData>) -> Result<Response<BoxStream<TracesData>>, Status> {
+        Ok(Response::new(Box::pin(tokio_stream::iter(
+            chunks.into_iter().map(Ok),
+        ))))
+    }
+
+    #[tonic::async_trait]
+    impl QueryService for Fake {
+        type GetTraceStream = BoxStream<TracesData>;
+        type FindTracesStream = BoxStream<TracesData>;
+
+        async fn get_trace(
+            &self,
+            request: Request<GetTraceRequest>,
+        ) -> Result<Response<BoxStream<TracesData>>, Status> {
+            let request = request.into_inner();
+            if request.trace_id != "01".repeat(16) {
+                return Err(Status::not_found(request.trace_id));
+            }
+            stream(vec![chunk(TRACE_ID, "first"), chunk(TRACE_ID, "second")])
+        }
+
+        async fn find_traces(
+            &self,
+            request: Request<FindTracesRequest>,
+        ) -> Result<Response<BoxStream<TracesData>>, Status> {
+            let query = request.into_inner().query.unwrap_or_default();
+            if query.service_name != KUBO || query.start_time_min.is_none() {
+                return Err(Status::invalid_argument("query"));
+            }
+            stream(vec![chunk([3; 16], "other")])
+        }
+
+        async fn get_services(
+            &self,
+            _request: Request<GetServicesRequest>,
+        ) -> Result<Response<GetServicesResponse>, Status> {
+            Err(Status::unimplemented("GetServices"))
+        }
+
+        async fn get_operations(
+            &self,
+            _request: Request<GetOperationsRequest>,
+        ) -> Result<Response<GetOperationsResponse>, Status> {
+            Err(Status::unimplemented("GetOperations"))
+        }
+    }
+
+    #[tokio::test]
+    async fn client_against_fake() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(QueryServiceServer::new(Fake))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let client = Client::connect(format!("http://{addr}")).await.unwrap();
+
+        let spans = client.trace(&"01".repeat(16)).await.unwrap();
+        assert_eq!(
+            spans.iter().map(|span| &*span.name).collect::<Vec<_>>(),
+            ["first", "second"]
+        );
+        assert!(client.trace(&"02".repeat(16)).await.is_err());
+
+        let other_spans = super::spans(
+            client
+                .find_traces(TraceQueryParameters {
+                    service_name: KUBO.into(),
+                    start_time_min: Some(Timestamp::default()),
+                    ..Default::default()
+                })
+                .await
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(other_spans.len(), 1);
+        assert_eq!(other_spans[0].trace_id, [3; 16]);
+        assert!(client.services().await.is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use reqwest::{RequestBuilder, Response, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    routing::{FindProvs, RoutingEvent},
+    traceparent::TraceParent,
+    Host,
+};
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    endpoint: String,
+    timeout: Option<Duration>,
+    traceparent: Option<TraceParent>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Error {
+    Message: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Published {
+    pub Name: String,
+    pub Value: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolved {
+    pub Path: String,
+}
+
+pub struct Events {
+    response: Response,
+    buf: Vec<u8>,
+    lines: VecDeque<(Duration, Vec<u8>)>,
+}
+
+impl Events {
+    pub async fn next(&mut self) -> anyhow::Result<Option<RoutingEvent>> {
+        loop {
+            if let Some((received, line)) = self.lines.pop_front() {
+                let find_provs = serde_json::from_slice::<FindProvs>(&line)?;
+                return Ok(Some(RoutingEvent {
+                    received,
+                    event: find_provs.try_into()?,
+                }));
+            }
+            let Some(chunk) = self.response.chunk().await? else {
+                if self.buf.iter().all(u8::is_ascii_whitespace) {
+                    return Ok(None);
+                }
+                let line = std::mem::take(&mut self.buf);
+                self.lines.push_back((UNIX_EPOCH.elapsed()?, line));
+                continue;
+            };
+            let received = UNIX_EPOCH.elapsed()?;
+            self.buf.extend_from_slice(&chunk);
+            while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+                let mut line = self.buf.split_off(pos + 1);
+                std::mem::swap(&mut line, &mut self.buf);
+                if !line.iter().all(u8::is_ascii_whitespace) {
+                    self.lines.push_back((received, line))
+                }
+            }
+        }
+    }
+
+    pub async fn collect(mut self) -> anyhow::Result<Vec<RoutingEvent>> {
+        let mut events = Vec::new();
+        while let Some(event) = self.next().await? {
+            events.push(event)
+        }
+        Ok(events)
+    }
+}
+
+impl Client {
+    // `endpoint` is the HTTP form of `Addresses.API`, e.g. `http://127.0.0.1:5001`
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+            timeout: None,
+            traceparent: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // kubo's spans of every following request join this trace
+    pub fn traceparent(mut self, traceparent: TraceParent) -> Self {
+        self.traceparent = Some(traceparent);
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn request(&self, command: &str) -> RequestBuilder {
+        let mut request = self
+            .http
+            .post(format!("{}/api/v0/{command}", self.endpoint));
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout)
+        }
+        if let Some(traceparent) = self.traceparent {
+            request = request.header("traceparent", traceparent.to_string())
+        }
+        request
+    }
+
+    async fn post(&self, command: &str, query: &[(&str, &str)]) -> anyhow::Result<Response> {
+        check(self.request(command).query(query).send().await?).await
+    }
+
+    async fn events(&self, command: &str, query: &[(&str, &str)]) -> anyhow::Result<Events> {
+        Ok(Events {
+            response: self.post(command, query).await?,
+            buf: Default::default(),
+            lines: Default::default(),
+        })
+    }
+
+    pub async fn routing_findprovs(
+        &self,
+        cid: &str,
+        num_providers: usize,
+    ) -> anyhow::Result<Events> {
+        self.events(
+            "routing/findprovs",
+            &[("arg", cid), ("num-providers", &num_providers.to_string())],
+        )
+        .await
+    }
+
+    pub async fn routing_findpeer(&self, peer_id: &str) -> anyhow::Result<Events> {
+        self.events("routing/findpeer", &[("arg", peer_id)]).await
+    }
+
+    pub async fn routing_provide(&self, cid: &str) -> anyhow::Result<Events> {
+        self.events("routing/provide", &[("arg", cid)]).await
+    }
+
+    // like `ipfs get -o /dev/null`, returns the size of the TAR archive
+    pub async fn get(&self, cid: &str) -> anyhow::Result<u64> {
+        let mut response = self
+            .post("get", &[("arg", cid), ("progress", "false")])
+            .await?;
+        let mut size = 0;
+        while let Some(chunk) = response.chunk().await? {
+            size += chunk.len() as u64
+        }
+        Ok(size)
+    }
+}
+
+async fn check(response: Response) -> anyhow::Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    Err(error(status, &response.text().await?))
+}
+
+fn error(status: impl Display, text: &str) -> anyhow::Error {
+    match serde_json::from_str::<Error>(text) {
+        Ok(err) => anyhow::anyhow!("{status}: {}", err.Message),
+        Err(_) => anyhow::anyhow!("{status}: {}", text.trim()),
+    }
+}
+
+// RPC calls made with curl on the daemon's host, so that the API needs to listen on loopback only
+// and calls are timed there, without the ssh round trip; the host needs curl
+#[derive(Debug, Clone)]
+pub struct HostClient {
+    host: Host,
+    port: u16,
+    timeout: Option<Duration>,
+    traceparent: Option<TraceParent>,
+}
+
+// the result of a call, and how long it took on the host even if it failed
+#[derive(Debug)]
+pub struct Timed<T> {
+    pub result: anyhow::Result<T>,
+    pub duration: Duration,
+}
+
+impl HostClient {
+    // `port` of `Addresses.API` on the loopback of `host`
+    pub fn new(host: Host, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            timeout: None,
+            traceparent: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // kubo's spans of every following request join this trace
+    pub fn traceparent(mut self, traceparent: TraceParent) -> Self {
+        self.traceparent = Some(traceparent);
+        self
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        command: &str,
+        query: &[(&str, &str)],
+    ) -> anyhow::Result<Timed<T>> {
+        let url = Url::parse_with_params(
+            &format!("http://127.0.0.1:{}/api/v0/{command}", self.port),
+            query,
+        )?;
+        let mut curl = String::from("curl -sS -X POST");
+        if let Some(timeout) = self.timeout {
+            curl += &format!(" --max-time {}", timeout.as_secs())
+        }
+        if let Some(traceparent) = self.traceparent {
+            curl += &format!(" -H 'traceparent: {traceparent}'")
+        }
+        let output = self
+            .host
+            .output(format!(
+                "{curl} -w '\\n%{{http_code}} %{{time_total}}' '{url}'"
+            ))
+            .await?;
+        // curl writes out the status and time even if the request fails, e.g. with 000 on timeout
+        let written = output
+            .stdout
+            .rsplit_once('\n')
+            .and_then(|(body, written)| Some((body, written.split_once(' ')?)));
+        let Some((body, (status, time))) = written else {
+            anyhow::bail!(
+                "unexpected curl output {:?}: {}",
+                output.stdout,
+                output.stderr.trim()
+            )
+        };
+        let result = match status {
+            "200" => serde_json::from_str(body).map_err(Into::into),
+            "000" => Err(anyhow::anyhow!("{}", output.stderr.trim())),
+            status => Err(error(status, body)),
+        };
+        Ok(Timed {
+            result,
+            duration: Duration::from_secs_f64(time.parse()?),
+        })
+    }
+
+    // publishes `path`, e.g. `/ipfs/<cid>`, under the IPNS name of `key`
+    pub async fn name_publish(&self, path: &str, key: &str) -> anyhow::Result<Timed<Published>> {
+        self.call("name/publish", &[("arg", path), ("key", key)])
+            .await
+    }
+
+    // `nocache` skips the local cache of resolved names
+    pub async fn name_resolve(&self, name: &str, nocache: bool) -> anyhow::Result<Timed<Resolved>> {
+        self.call(
+            "name/resolve",
+            &[("arg", name), ("nocache", &nocache.to_string())],
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::RawQuery, http::StatusCode, routing::post, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn host_client_with_curl() {
+        let router = Router::new()
+            .route(
+                "/api/v0/name/resolve",
+                // echoes the query as the path
+                post(|RawQuery(query): RawQuery| async move {
+                    format!(r#"{{"Path": "{}"}}"#, query.unwrap_or_default())
+                }),
+            )
+            .route(
+                "/api/v0/name/publish",
+                post(|| async {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        r#"{"Message": "no key", "Code": 0, "Type": "error"}"#,
+                    )
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let client = HostClient::new(Host::local(), port).timeout(Duration::from_secs(10));
+
+        let resolved = client.name_resolve("/ipns/k51 name", true).await.unwrap();
+        assert_eq!(
+            resolved.result.unwrap().Path,
+            "arg=%2Fipns%2Fk51+name&nocache=true"
+        );
+        assert!(resolved.duration < Duration::from_secs(10));
+
+        let published = client.name_publish("/ipfs/cid", "self").await.unwrap();
+        assert_eq!(published.result.unwrap_err().to_string(), "500: no key");
+
+        // nothing listening
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let refused = HostClient::new(Host::local(), port)
+            .name_resolve("/ipns/name", false)
+            .await
+            .unwrap();
+        assert!(refused.result.unwrap_err().to_string().contains("curl"));
+    }
+}
@@ -0,0 +1,21 @@
+pub mod analysis;
+pub mod cleanup;
+pub mod config;
+pub mod delegated;
+pub mod experiments;
+pub mod gateway;
+pub mod geoip;
+pub mod host;
+pub mod jaeger;
+pub mod kubo;
+pub mod otlp;
+pub mod peer;
+pub mod ports;
+pub mod ready;
+pub mod routing;
+pub mod traceparent;
+pub mod traces;
+pub mod transport;
+
+pub use host::{Host, Output, Session, Transport};
+pub use peer::EphemeralPeer;
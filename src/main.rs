@@ -0,0 +1,281 @@
+use std::{
+    io::{stdin, stdout, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use clap::{Parser, Subcommand};
+use izuko::{
+    analysis::{self, tree::Query},
+    cleanup,
+    config::{Config, Overrides},
+    experiments, Host,
+};
+
+/// Measure content routing and retrieval in the IPFS network
+///
+/// Experiments run against kubo on the configured host, through ssh or on this machine with
+/// `--host local`. Values not given as flags are read from the experiment file (izuko.toml).
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    overrides: Overrides,
+    /// Remove ephemeral peers and daemons left over by crashed runs without asking
+    #[arg(long, global = true)]
+    reap: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Dump provider records of a CID found by the host daemon, with their routes
+    DumpProviders,
+    /// Find providers of a CID from many fresh ephemeral peers
+    FindProviders,
+    /// Download a CID from each dumped provider through a dedicated ephemeral peer
+    ProfileProviders,
+    /// Download a CID from each provider found, with fresh peers and parallel sessions
+    Qoe,
+    /// Download fresh random data provided by the canary host and retrieve its trace
+    GetOnce,
+    /// Download a popular CID with a freshly bootstrapped daemon and retrieve its trace
+    GetHot,
+    /// Provide fresh random data from the host and retrieve its trace
+    Provide,
+    /// Publish an IPNS record from the canary host, resolve it with fresh peers and retrieve the traces
+    Ipns,
+    /// Install the kubo build in ../kubo on the host and configure it
+    InitInstance {
+        /// Also start a Jaeger all-in-one container as telemetry collector
+        #[arg(long)]
+        telemetry: bool,
+    },
+    /// Convert or link saved traces
+    Trace {
+        #[command(subcommand)]
+        command: TraceCommand,
+    },
+    /// Compute metrics from saved traces
+    Analyze {
+        #[command(subcommand)]
+        analysis: Analysis,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TraceCommand {
+    /// Chrome trace event JSON that opens offline in Perfetto or chrome://tracing
+    Export {
+        /// Trace file, `<start time>.json`
+        path: PathBuf,
+        /// [default: <start time>.chrome.json next to the trace]
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Retrieval records of the other traces serving each retrieval, linked by CID and provider
+    Link {
+        /// Trace directories, by default the traces of get-once and get-hot in the output directory
+        dirs: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum Analysis {
+    /// Provider churn across all dumps of a CID: sessions, address changes and old dump liveness
+    Churn {
+        /// Dump directories, by default the one of the CID in the output directory
+        dirs: Vec<PathBuf>,
+        /// Also save the per provider table as CSV
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Find, first block, transfer and total latencies of retrieval traces
+    Get {
+        /// Trace directories, by default the traces of get-once and get-hot in the output directory
+        dirs: Vec<PathBuf>,
+        /// Also save the table as CSV
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Critical path of a retrieval trace, broken down into phases
+    CriticalPath {
+        /// Trace file, `<start time>.json`
+        path: PathBuf,
+        /// Also save the breakdown as JSON
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+    /// Spans of a saved trace matching all the filters given
+    Spans {
+        /// Trace file, `<start time>.json`
+        path: PathBuf,
+        /// Span name
+        #[arg(long)]
+        name: Option<String>,
+        /// String attribute, as `key=value`
+        #[arg(long, value_parser = parse_attr)]
+        attr: Vec<(String, String)>,
+        /// Name of an ancestor span
+        #[arg(long)]
+        ancestor: Option<String>,
+        /// Search the other traces in the time range instead
+        #[arg(long)]
+        other: bool,
+        /// Also print the spans under each match
+        #[arg(long)]
+        tree: bool,
+    },
+    /// Country, city and ASN of the peers in dump-providers JSON or find-providers CSV files
+    Peers {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Success of provider record puts to the closest peers, with the peer addresses
+    Provide {
+        /// Trace directories, by default the traces of provide in the output directory
+        dirs: Vec<PathBuf>,
+        /// Also save the table as CSV
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli.overrides)?;
+    if !matches!(
+        cli.command,
+        Command::InitInstance { .. } | Command::Trace { .. } | Command::Analyze { .. }
+    ) {
+        let mut hosts = vec![config.host()?];
+        hosts.extend(config.canary_host().ok());
+        sweep(&hosts, cli.reap).await?;
+    }
+    // on interruption the experiment is dropped, its peers leave their cleanups pending
+    let result = tokio::select! {
+        result = run(cli.command, config) => result,
+        signal = cleanup::interrupted() => Err(anyhow::anyhow!("interrupted by {}", signal?)),
+    };
+    cleanup::reap().await;
+    result
+}
+
+async fn run(command: Command, config: Config) -> anyhow::Result<()> {
+    match command {
+        Command::DumpProviders => experiments::dump_providers::run(config).await,
+        Command::FindProviders => experiments::find_providers::run(config).await,
+        Command::ProfileProviders => experiments::profile_providers::run(config).await,
+        Command::Qoe => experiments::qoe::run(config).await,
+        Command::GetOnce => experiments::get_once::run(config).await,
+        Command::GetHot => experiments::get_hot::run(config).await,
+        Command::Provide => experiments::provide::run(config).await,
+        Command::Ipns => experiments::ipns::run(config).await,
+        Command::InitInstance { telemetry } => {
+            experiments::init_instance::run(config, telemetry).await
+        }
+        Command::Trace {
+            command: TraceCommand::Export { path, output },
+        } => analysis::chrome::export(path, output).await,
+        Command::Trace {
+            command: TraceCommand::Link { dirs },
+        } => analysis::record::run(config, dirs).await,
+        Command::Analyze {
+            analysis: Analysis::Churn { dirs, csv },
+        } => analysis::churn::run(config, dirs, csv).await,
+        Command::Analyze {
+            analysis: Analysis::Get { dirs, csv },
+        } => analysis::get::run(config, dirs, csv).await,
+        Command::Analyze {
+            analysis: Analysis::CriticalPath { path, json },
+        } => analysis::critical_path::run(path, json).await,
+        Command::Analyze {
+            analysis:
+                Analysis::Spans {
+                    path,
+                    name,
+                    attr,
+                    ancestor,
+                    other,
+                    tree,
+                },
+        } => {
+            let mut query = Query::new();
+            if let Some(name) = name {
+                query = query.name(name)
+            }
+            for (key, value) in attr {
+                query = query.attr(key, &*value)
+            }
+            if let Some(ancestor) = ancestor {
+                query = query.ancestor(Query::new().name(ancestor))
+            }
+            analysis::spans::run(path, query, other, tree).await
+        }
+        Command::Analyze {
+            analysis: Analysis::Peers { paths },
+        } => analysis::peers::run(config, paths).await,
+        Command::Analyze {
+            analysis: Analysis::Provide { dirs, csv },
+        } => analysis::provide::run(config, dirs, csv).await,
+    }
+}
+
+fn parse_attr(attr: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = attr
+        .split_once('=')
+        .ok_or(anyhow::anyhow!("expect key=value"))?;
+    Ok((key.into(), value.into()))
+}
+
+async fn sweep(hosts: &[Host], reap: bool) -> anyhow::Result<()> {
+    let mut leftovers = Vec::new();
+    for host in hosts {
+        let (in_use, stale) = cleanup::leftovers(host)
+            .await?
+            .into_iter()
+            .partition::<Vec<_>, _>(|leftover| leftover.in_use());
+        for leftover in &in_use {
+            println!(
+                "* Keep {} on {}, in use by {}",
+                leftover.name(),
+                host.name(),
+                leftover.owner.as_deref().unwrap_or_default()
+            )
+        }
+        if !stale.is_empty() {
+            leftovers.push((host, stale))
+        }
+    }
+    if leftovers.is_empty() {
+        return Ok(());
+    }
+    for (host, stale) in &leftovers {
+        println!(
+            "* Found {} leftover(s) of crashed runs on {}",
+            stale.len(),
+            host.name()
+        );
+        for leftover in stale {
+            println!("  {}", leftover.name())
+        }
+    }
+    let reap = reap
+        || stdin().is_terminal() && {
+            print!("? Shut down and remove them [y/N] ");
+            stdout().flush()?;
+            let mut answer = String::new();
+            stdin().read_line(&mut answer)?;
+            answer.trim().eq_ignore_ascii_case("y")
+        };
+    if reap {
+        println!("* Reap leftovers");
+        for (host, stale) in &leftovers {
+            cleanup::reap_leftovers(host, stale).await?
+        }
+    } else {
+        println!("! Keep leftovers, pass --reap to remove them")
+    }
+    Ok(())
+}
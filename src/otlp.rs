@@ -0,0 +1,233 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::{
+        trace_service_server::{TraceService, TraceServiceServer},
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    },
+    trace::v1::Span,
+};
+use prost::Message;
+use tokio::{net::TcpListener, task::JoinHandle, time::sleep};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{codegen::async_trait, Request, Response, Status};
+
+use crate::{traceparent::hex, traces, Host, Session};
+
+// every span received in this run
+#[derive(Debug, Clone, Default)]
+pub struct Store {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+impl Store {
+    fn export(&self, request: ExportTraceServiceRequest) -> anyhow::Result<()> {
+        self.spans
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .extend(traces::flatten(request.resource_spans));
+        Ok(())
+    }
+
+    pub fn spans(&self) -> anyhow::Result<Vec<Span>> {
+        Ok(self
+            .spans
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .clone())
+    }
+
+    // `trace_id` in hex
+    pub fn trace(&self, trace_id: &str) -> anyhow::Result<Vec<Span>> {
+        Ok(self
+            .spans()?
+            .into_iter()
+            .filter(|span| hex(&span.trace_id) == trace_id)
+            .collect())
+    }
+
+    // all spans of the traces that have a span starting in the range, like Jaeger's `FindTraces`
+    pub fn traces_in(&self, start: u64, end: u64) -> anyhow::Result<Vec<Span>> {
+        let spans = self.spans()?;
+        let trace_ids = spans
+            .iter()
+            .filter(|span| (start..=end).contains(&span.start_time_unix_nano))
+            .map(|span| span.trace_id.clone())
+            .collect::<HashSet<_>>();
+        Ok(spans
+            .into_iter()
+            .filter(|span| trace_ids.contains(&span.trace_id))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TraceService for Store {
+    async fn export(
+        &self,
+        request: Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        Store::export(self, request.into_inner())
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(Default::default()))
+    }
+}
+
+// `POST /v1/traces` of OTLP/HTTP, in either protobuf or JSON encoding
+async fn export_http(
+    State(store): State<Store>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let json = headers
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    let request = if json {
+        serde_json::from_slice(&body).map_err(anyhow::Error::from)
+    } else {
+        ExportTraceServiceRequest::decode(body).map_err(anyhow::Error::from)
+    };
+    match request.and_then(|request| store.export(request)) {
+        Ok(()) if json => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, "application/json")],
+            b"{}".to_vec(),
+        ),
+        Ok(()) => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, "application/x-protobuf")],
+            ExportTraceServiceResponse::default().encode_to_vec(),
+        ),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            [(CONTENT_TYPE, "text/plain")],
+            err.to_string().into_bytes(),
+        ),
+    }
+}
+
+// OTLP receiver that kubo's exporter on the host reaches at the default `localhost:4317` (gRPC) and
+// `localhost:4318` (HTTP), so no Jaeger container is needed
+#[derive(Debug)]
+pub struct Receiver {
+    store: Store,
+    servers: Vec<JoinHandle<()>>,
+    // reverse forwarding of the OTLP ports for remote hosts
+    tunnels: Vec<Session>,
+}
+
+impl Receiver {
+    pub const GRPC_PORT: u16 = 4317;
+    pub const HTTP_PORT: u16 = 4318;
+
+    pub async fn start(host: &Host) -> anyhow::Result<Self> {
+        let store = Store::default();
+        let mut receiver = Self {
+            store: store.clone(),
+            servers: Default::default(),
+            tunnels: Default::default(),
+        };
+        // a local kubo dials the receiver directly, the ports are forwarded for remote ones
+        let bind = |port| async move {
+            let port = if host.is_local() { port } else { 0 };
+            anyhow::Ok(TcpListener::bind(("127.0.0.1", port)).await?)
+        };
+
+        let grpc = bind(Self::GRPC_PORT).await?;
+        let grpc_port = grpc.local_addr()?.port();
+        let service = TraceServiceServer::new(store.clone()).max_decoding_message_size(100 << 20);
+        receiver.servers.push(tokio::spawn(async move {
+            let result = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(grpc))
+                .await;
+            if let Err(err) = result {
+                println!("! OTLP/gRPC receiver: {err}")
+            }
+        }));
+
+        let http = bind(Self::HTTP_PORT).await?;
+        let http_port = http.local_addr()?.port();
+        let router = Router::new()
+            .route("/v1/traces", post(export_http))
+            .layer(DefaultBodyLimit::max(100 << 20))
+            .with_state(store);
+        receiver.servers.push(tokio::spawn(async move {
+            if let Err(err) = axum::serve(http, router).await {
+                println!("! OTLP/HTTP receiver: {err}")
+            }
+        }));
+
+        for (remote_port, local_port) in
+            [(Self::GRPC_PORT, grpc_port), (Self::HTTP_PORT, http_port)]
+        {
+            if let Some(tunnel) = host.forward_remote(remote_port, local_port)? {
+                receiver.tunnels.push(tunnel)
+            }
+        }
+        // forwarding fails e.g. when a Jaeger container still listens on the host
+        sleep(Duration::from_secs(1)).await;
+        if receiver.tunnels.iter().any(Session::is_finished) {
+            receiver.close().await?;
+            anyhow::bail!(
+                "cannot forward OTLP ports {} and {} on {}, are they in use?",
+                Self::GRPC_PORT,
+                Self::HTTP_PORT,
+                host.name()
+            )
+        }
+        Ok(receiver)
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub async fn save_trace(
+        &self,
+        trace_id: &str,
+        dir: impl AsRef<Path>,
+    ) -> anyhow::Result<PathBuf> {
+        let trace_spans = self.store.trace(trace_id)?;
+        anyhow::ensure!(!trace_spans.is_empty(), "empty trace {trace_id}");
+        let (start, end) = traces::range(&trace_spans);
+        let other_spans = self
+            .store
+            .traces_in(start, end)?
+            .into_iter()
+            .filter(|span| span.trace_id != trace_spans[0].trace_id)
+            .collect::<Vec<_>>();
+        traces::save(trace_id, &trace_spans, &other_spans, dir).await
+    }
+
+    pub async fn close(mut self) -> anyhow::Result<()> {
+        for server in self.servers.drain(..) {
+            server.abort()
+        }
+        for tunnel in self.tunnels.drain(..) {
+            tunnel.kill().await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        for server in &self.servers {
+            server.abort()
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use crate::{
+    cleanup::{self, Guard},
+    kubo::HostClient,
+    ports::{Lease, Ports},
+    ready::Probe,
+    Host, Output, Session,
+};
+
+// a throwaway kubo repo and daemon on `host`, identified by `index` among the concurrent ones
+#[derive(Debug)]
+pub struct EphemeralPeer {
+    host: Host,
+    index: usize,
+    lease: Lease,
+    id: String,
+    daemon: Option<Session>,
+    ready_after: Option<Duration>,
+    // the daemon exports spans to the OTLP collector on the host, e.g. `traces::Traces`
+    export_traces: bool,
+    // shuts down the daemon and removes the repo, until `close`
+    cleanup: Option<Guard>,
+}
+
+impl EphemeralPeer {
+    // columns of `readiness_fields` in per-session CSVs
+    pub const READINESS_HEADER: &str = "ready,ready seconds";
+
+    pub async fn init(
+        host: Host,
+        index: usize,
+        lease: Lease,
+        routing: bool,
+    ) -> anyhow::Result<Self> {
+        let mut peer = Self {
+            host,
+            index,
+            lease,
+            id: Default::default(),
+            daemon: None,
+            ready_after: None,
+            export_traces: false,
+            cleanup: None,
+        };
+        peer.cleanup = Some(cleanup::register(
+            &peer.host,
+            format!("{}; rm -rf {}", peer.command("ipfs shutdown"), peer.repo()),
+        ));
+        peer.host
+            .output(
+                format!("export IPFS_PATH={}", peer.repo())
+                    + "; ipfs shutdown"
+                    + "; ipfs init --profile server"
+                    + "; ipfs key rm old"
+                    + "; ipfs pin ls -t recursive -q | ipfs pin rm"
+                    + "; ipfs key rotate -o old"
+                    + " && ipfs repo gc"
+                    + if routing {
+                        ""
+                    } else {
+                        " && ipfs config Routing.Type none"
+                    }
+                    + &format!(" && ipfs config Addresses.API {}", peer.api())
+                    + &format!(" && ipfs config Addresses.Gateway {}", peer.gateway())
+                    + &format!(
+                        " && ipfs config --json Addresses.Swarm '{}'",
+                        serde_json::to_string(&peer.swarm())?
+                    ),
+            )
+            .await?
+            .check()?;
+        peer.id = peer.configured_id().await?;
+        Ok(peer)
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // time the last `start` took until the daemon became ready
+    pub fn ready_after(&self) -> Option<Duration> {
+        self.ready_after
+    }
+
+    // whether the last `start` became ready and after how long, no seconds if it did not
+    pub fn readiness_fields(&self) -> String {
+        match self.ready_after {
+            Some(ready_after) => format!("true,{}", ready_after.as_secs_f32()),
+            None => "false,".into(),
+        }
+    }
+
+    // takes effect from the next `start`
+    pub fn export_traces(&mut self, export_traces: bool) {
+        self.export_traces = export_traces
+    }
+
+    pub fn ports(&self) -> Ports {
+        self.lease.ports()
+    }
+
+    // created by `PortAllocator::allocate`
+    pub fn repo(&self) -> String {
+        self.ports().repo()
+    }
+
+    pub fn api(&self) -> String {
+        format!("/ip4/127.0.0.1/tcp/{}", self.ports().api)
+    }
+
+    pub fn gateway(&self) -> String {
+        format!("/ip4/127.0.0.1/tcp/{}", self.ports().gateway)
+    }
+
+    pub fn swarm(&self) -> Vec<String> {
+        let port = self.ports().swarm;
+        ["/ip4/0.0.0.0", "/ip6/::"]
+            .into_iter()
+            .flat_map(|ip| {
+                [
+                    format!("{ip}/tcp/{port}"),
+                    format!("{ip}/udp/{port}/quic-v1"),
+                    format!("{ip}/udp/{port}/quic-v1/webtransport"),
+                ]
+            })
+            .collect()
+    }
+
+    // RPC client that calls with curl on the host, see `HostClient`
+    pub fn host_client(&self) -> HostClient {
+        HostClient::new(self.host.clone(), self.ports().api)
+    }
+
+    pub fn command(&self, command: &str) -> String {
+        format!("IPFS_PATH={} {command}", self.repo())
+    }
+
+    pub async fn run(&self, command: impl AsRef<str>) -> anyhow::Result<()> {
+        self.host.run(self.command(command.as_ref())).await
+    }
+
+    pub async fn status(
+        &self,
+        command: impl AsRef<str>,
+    ) -> anyhow::Result<std::process::ExitStatus> {
+        self.host.status(self.command(command.as_ref())).await
+    }
+
+    pub async fn output(&self, command: impl AsRef<str>) -> anyhow::Result<Output> {
+        self.host.output(self.command(command.as_ref())).await
+    }
+
+    async fn configured_id(&self) -> anyhow::Result<String> {
+        Ok(self
+            .output("ipfs config Identity.PeerID")
+            .await?
+            .check()?
+            .trim()
+            .into())
+    }
+
+    pub async fn start(&mut self) -> anyhow::Result<Duration> {
+        self.start_with(&Probe::default()).await
+    }
+
+    pub async fn start_with(&mut self, probe: &Probe) -> anyhow::Result<Duration> {
+        anyhow::ensure!(self.daemon.is_none(), "daemon already started");
+        self.ready_after = None;
+        let daemon = self.host.spawn(self.command(if self.export_traces {
+            "OTEL_EXPORTER_OTLP_INSECURE=true OTEL_TRACES_EXPORTER=otlp ipfs daemon"
+        } else {
+            "ipfs daemon"
+        }))?;
+        let ready_after = match probe
+            .wait(&self.host, |command| self.command(command), Some(&daemon))
+            .await
+        {
+            Ok(ready_after) => ready_after,
+            Err(err) => {
+                // the API may be up even if other conditions are not met
+                let _ = self.output("ipfs shutdown").await;
+                daemon.kill().await?;
+                return Err(err);
+            }
+        };
+        self.daemon = Some(daemon);
+        self.ready_after = Some(ready_after);
+        Ok(ready_after)
+    }
+
+    pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        let Some(daemon) = self.daemon.take() else {
+            return Ok(());
+        };
+        self.run("ipfs shutdown").await?;
+        daemon.wait().await?;
+        Ok(())
+    }
+
+    // the daemon must be shut down
+    pub async fn rotate_identity(&mut self, routing: bool) -> anyhow::Result<()> {
+        anyhow::ensure!(self.daemon.is_none(), "daemon is running");
+        self.host
+            .run(
+                format!("export IPFS_PATH={}", self.repo())
+                    + "; ipfs key rm old"
+                    + "; ipfs key rotate -o old"
+                    + if routing {
+                        ""
+                    } else {
+                        " && ipfs config Routing.Type none"
+                    },
+            )
+            .await?;
+        self.id = self.configured_id().await?;
+        Ok(())
+    }
+
+    pub async fn close(mut self) -> anyhow::Result<()> {
+        let result = self.shutdown().await;
+        self.host.run(format!("rm -rf {}", self.repo())).await?;
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup.dismiss()
+        }
+        result
+    }
+}
+
+impl Drop for EphemeralPeer {
+    // cleans up in background, what does not finish in time is left to `cleanup::reap`
+    fn drop(&mut self) {
+        let Some(cleanup) = self.cleanup.take() else {
+            return;
+        };
+        let daemon = self.daemon.take();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = cleanup.run().await;
+                if let Some(daemon) = daemon {
+                    let _ = daemon.wait().await;
+                }
+            });
+        }
+    }
+}
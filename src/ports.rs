@@ -0,0 +1,167 @@
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, RandomState},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{cleanup, Host};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Ports {
+    pub api: u16,
+    pub gateway: u16,
+    // both TCP and UDP (QUIC, WebTransport)
+    pub swarm: u16,
+}
+
+impl Ports {
+    // repo of the peer on these ports, API port is unique on the host as long as the peer lives
+    pub fn repo(&self) -> String {
+        format!("/tmp/ipfs-{}", self.api)
+    }
+}
+
+// hands out ports that are neither listened on by the host, nor named by a repo on the host, nor
+// leased to another session; the repo of the API port is created to claim it against concurrent
+// campaigns on the same host
+#[derive(Debug, Clone)]
+pub struct PortAllocator {
+    host: Host,
+    range: Range<u16>,
+    reserved: Arc<Mutex<HashSet<u16>>>,
+    history: Arc<Mutex<Vec<Allocation>>>,
+    allocating: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Allocation {
+    pub index: usize,
+    #[serde(flatten)]
+    pub ports: Ports,
+}
+
+#[derive(Debug)]
+pub struct Lease {
+    ports: Ports,
+    reserved: Arc<Mutex<HashSet<u16>>>,
+}
+
+impl PortAllocator {
+    pub fn new(host: Host) -> Self {
+        // below the default Linux ephemeral range 32768-60999
+        Self::with_range(host, 20000..32768)
+    }
+
+    pub fn with_range(host: Host, range: Range<u16>) -> Self {
+        Self {
+            host,
+            range,
+            reserved: Default::default(),
+            history: Default::default(),
+            allocating: Default::default(),
+        }
+    }
+
+    async fn listening(&self) -> anyhow::Result<HashSet<u16>> {
+        let output = self.host.output("ss -Htuln").await?.check()?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(4)?.rsplit_once(':'))
+            .filter_map(|(_, port)| port.parse().ok())
+            .collect())
+    }
+
+    // ports of the `/tmp/ipfs-<port>` repos, including those of other campaigns not yet listening
+    async fn repos(&self) -> anyhow::Result<HashSet<u16>> {
+        let output = self.host.output("ls -d /tmp/ipfs-*").await?;
+        Ok(output
+            .stdout
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("/tmp/ipfs-")?.parse().ok())
+            .collect())
+    }
+
+    fn pick(&self, index: usize, taken: &HashSet<u16>) -> anyhow::Result<Ports> {
+        let reserved = self
+            .reserved
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        let len = self.range.len();
+        let offset = RandomState::new().hash_one(index) as usize % len;
+        let mut free = (0..len)
+            .map(|i| self.range.start + ((offset + i) % len) as u16)
+            .filter(|port| !taken.contains(port) && !reserved.contains(port));
+        let (Some(api), Some(gateway), Some(swarm)) = (free.next(), free.next(), free.next())
+        else {
+            anyhow::bail!("no free port in {:?} on {}", self.range, self.host.name())
+        };
+        Ok(Ports {
+            api,
+            gateway,
+            swarm,
+        })
+    }
+
+    // `index` identifies the session in `history`
+    pub async fn allocate(&self, index: usize) -> anyhow::Result<Lease> {
+        let _allocating = self.allocating.lock().await;
+        let mut taken = self.listening().await?;
+        taken.extend(self.repos().await?);
+        let ports = loop {
+            let ports = self.pick(index, &taken)?;
+            // `mkdir` fails if another campaign claimed the port since `repos`
+            if self
+                .host
+                .output(format!(
+                    "mkdir {} && {}",
+                    ports.repo(),
+                    cleanup::mark(&ports.repo())
+                ))
+                .await?
+                .success()
+            {
+                break ports;
+            }
+            taken.insert(ports.api);
+        };
+        self.reserved
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .extend([ports.api, ports.gateway, ports.swarm]);
+        self.history
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .push(Allocation { index, ports });
+        Ok(Lease {
+            ports,
+            reserved: self.reserved.clone(),
+        })
+    }
+
+    pub fn history(&self) -> anyhow::Result<Vec<Allocation>> {
+        Ok(self
+            .history
+            .lock()
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .clone())
+    }
+}
+
+impl Lease {
+    pub fn ports(&self) -> Ports {
+        self.ports
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if let Ok(mut reserved) = self.reserved.lock() {
+            for port in [self.ports.api, self.ports.gateway, self.ports.swarm] {
+                reserved.remove(&port);
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fmt::Display, time::Duration};
+
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::{Host, Session};
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    // RPC API answers
+    ApiUp,
+    // at least this many peers in the WAN DHT routing table
+    RoutingTable(usize),
+    // at least this many connected peers
+    Peers(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub deadline: Duration,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    pub conditions: Vec<Condition>,
+}
+
+impl Default for Probe {
+    fn default() -> Self {
+        Self {
+            deadline: Duration::from_secs(60),
+            backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+            conditions: vec![Condition::ApiUp],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NotReady {
+    pub elapsed: Duration,
+    pub pending: Condition,
+}
+
+impl Display for NotReady {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "daemon not ready after {:?}, waiting for {:?}",
+            self.elapsed, self.pending
+        )
+    }
+}
+
+impl std::error::Error for NotReady {}
+
+impl Probe {
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn condition(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    // `command` prepares an `ipfs` invocation for the probed daemon, e.g. sets `IPFS_PATH`
+    // returns the time until all conditions hold, fails with `NotReady` on deadline
+    pub async fn wait(
+        &self,
+        host: &Host,
+        command: impl Fn(&str) -> String,
+        daemon: Option<&Session>,
+    ) -> anyhow::Result<Duration> {
+        let start = Instant::now();
+        let not_ready = |pending: &Condition| {
+            anyhow::Error::from(NotReady {
+                elapsed: start.elapsed(),
+                pending: pending.clone(),
+            })
+        };
+        let mut backoff = self.backoff;
+        for condition in &self.conditions {
+            loop {
+                // a daemon whose API accepts but never answers, or a stalled ssh, must not hang
+                let check = timeout(
+                    self.deadline.saturating_sub(start.elapsed()),
+                    condition.check(host, &command),
+                );
+                let Ok(ready) = check.await else {
+                    return Err(not_ready(condition));
+                };
+                if ready? {
+                    break;
+                }
+                if daemon.is_some_and(Session::is_finished) {
+                    anyhow::bail!("daemon exited before ready")
+                }
+                if start.elapsed() + backoff > self.deadline {
+                    return Err(not_ready(condition));
+                }
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(self.max_backoff)
+            }
+        }
+        Ok(start.elapsed())
+    }
+}
+
+impl Condition {
+    async fn check(&self, host: &Host, command: impl Fn(&str) -> String) -> anyhow::Result<bool> {
+        Ok(match self {
+            Self::ApiUp => host.output(command("ipfs stats bw")).await?.success(),
+            Self::RoutingTable(count) => {
+                let output = host.output(command("ipfs stats dht wan")).await?;
+                // the first line reads `DHT wan (<n> peers):`
+                output.success()
+                    && output
+                        .stdout
+                        .split_once('(')
+                        .and_then(|(_, rest)| rest.split_once(' '))
+                        .and_then(|(n, _)| n.parse::<usize>().ok())
+                        .is_some_and(|n| n >= *count)
+            }
+            Self::Peers(count) => {
+                let output = host.output(command("ipfs swarm peers")).await?;
+                output.success() && output.stdout.lines().count() >= *count
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hung_check_times_out() {
+        let probe = Probe::default().deadline(Duration::from_millis(300));
+        let start = Instant::now();
+        let err = probe
+            .wait(&Host::local(), |_| "sleep 10".into(), None)
+            .await
+            .unwrap_err();
+        assert!(err.is::<NotReady>());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
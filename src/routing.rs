@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+// wire format of kubo `routing/*` responses, one per line
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct FindProvs {
+    #[serde(default)]
+    pub Extra: String,
+    #[serde(default)]
+    pub ID: String,
+    pub Responses: Option<Vec<FindProvsResponse>>,
+    pub Type: i32,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindProvsResponse {
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub Addrs: Vec<String>,
+    pub ID: String,
+}
+
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+// routing system a provider record is found by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    // kubo `routing/findprovs`
+    Dht,
+    // HTTP Delegated Routing V1 API, e.g. an IPNI indexer
+    Delegated,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Dht => "dht",
+            Self::Delegated => "delegated",
+        }
+    }
+}
+
+// a provider record tagged with its source, saved as the record with an extra `Source` field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tagged {
+    #[serde(rename = "Source")]
+    pub source: Source,
+    #[serde(flatten)]
+    pub response: FindProvsResponse,
+}
+
+pub fn tag(
+    responses: impl IntoIterator<Item = FindProvsResponse>,
+    source: Source,
+) -> impl Iterator<Item = Tagged> {
+    responses
+        .into_iter()
+        .map(move |response| Tagged { source, response })
+}
+
+// provider IDs by the set of sources finding them, e.g. `[dht]`, `[delegated]` and
+// `[dht, delegated]`, to compare the sources for a CID
+pub fn by_sources(records: &[Tagged]) -> BTreeMap<Vec<Source>, Vec<&str>> {
+    let mut sources = BTreeMap::<&str, BTreeSet<Source>>::new();
+    for record in records {
+        sources
+            .entry(&record.response.ID)
+            .or_default()
+            .insert(record.source);
+    }
+    let mut ids = BTreeMap::<_, Vec<_>>::new();
+    for (id, sources) in sources {
+        ids.entry(sources.into_iter().collect())
+            .or_default()
+            .push(id)
+    }
+    ids
+}
+
+pub fn print_sources(records: &[Tagged]) {
+    for (sources, ids) in by_sources(records) {
+        let sources = sources.iter().map(Source::name).collect::<Vec<_>>();
+        println!("* {} provider(s) by {}", ids.len(), sources.join(" and "))
+    }
+}
+
+// mirrors `routing.QueryEventType` of go-libp2p-core
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum QueryEvent {
+    SendingQuery {
+        peer: String,
+    },
+    PeerResponse {
+        peer: String,
+        closer: Vec<FindProvsResponse>,
+    },
+    FinalPeer {
+        peer: FindProvsResponse,
+    },
+    QueryError {
+        peer: String,
+        error: String,
+    },
+    Provider {
+        from: String,
+        providers: Vec<FindProvsResponse>,
+    },
+    Value {
+        from: String,
+        value: String,
+    },
+    AddingPeer {
+        peer: String,
+    },
+    DialingPeer {
+        peer: String,
+    },
+}
+
+impl TryFrom<FindProvs> for QueryEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: FindProvs) -> Result<Self, Self::Error> {
+        let FindProvs {
+            Extra: extra,
+            ID: id,
+            Responses: responses,
+            Type: ty,
+        } = value;
+        let responses = responses.unwrap_or_default();
+        Ok(match ty {
+            0 => Self::SendingQuery { peer: id },
+            1 => Self::PeerResponse {
+                peer: id,
+                closer: responses,
+            },
+            2 => Self::FinalPeer {
+                peer: responses
+                    .into_iter()
+                    .next()
+                    .ok_or(anyhow::anyhow!("final peer event without response"))?,
+            },
+            3 => Self::QueryError {
+                peer: id,
+                error: extra,
+            },
+            4 => Self::Provider {
+                from: id,
+                providers: responses,
+            },
+            5 => Self::Value {
+                from: id,
+                value: extra,
+            },
+            6 => Self::AddingPeer { peer: id },
+            7 => Self::DialingPeer { peer: id },
+            ty => anyhow::bail!("unknown query event type {ty}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingEvent {
+    // since UNIX epoch, taken when the line carrying the event arrives
+    pub received: Duration,
+    #[serde(flatten)]
+    pub event: QueryEvent,
+}
+
+pub fn providers(events: &[RoutingEvent]) -> impl Iterator<Item = &FindProvsResponse> {
+    events.iter().flat_map(|event| match &event.event {
+        QueryEvent::Provider { providers, .. } => &providers[..],
+        _ => &[],
+    })
+}
+
+pub fn final_peer(events: &[RoutingEvent]) -> Option<&FindProvsResponse> {
+    events.iter().find_map(|event| match &event.event {
+        QueryEvent::FinalPeer { peer } => Some(peer),
+        _ => None,
+    })
+}
@@ -0,0 +1,44 @@
+use std::{
+    fmt::Display,
+    hash::{BuildHasher, RandomState},
+    time::UNIX_EPOCH,
+};
+
+// W3C trace context of a measured operation, passed to kubo so that the operation's spans land in
+// a trace known in advance, see https://www.w3.org/TR/trace-context/#traceparent-header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceParent {
+    pub trace_id: [u8; 16],
+    // the span that kubo's spans are children of, never reported itself
+    pub parent_id: [u8; 8],
+}
+
+impl TraceParent {
+    pub fn random() -> Self {
+        let seed = UNIX_EPOCH.elapsed().unwrap_or_default().as_nanos();
+        let [high, low, parent] = [0u8, 1, 2].map(|i| RandomState::new().hash_one((seed, i)));
+        let mut trace_id = [0; 16];
+        trace_id[..8].copy_from_slice(&high.to_be_bytes());
+        trace_id[8..].copy_from_slice(&low.to_be_bytes());
+        Self {
+            trace_id,
+            parent_id: parent.to_be_bytes(),
+        }
+    }
+
+    // lowercase hex, as Jaeger expects in `GetTrace`
+    pub fn trace_id(&self) -> String {
+        hex(&self.trace_id)
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// the header value, version 00 and sampled
+impl Display for TraceParent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00-{}-{}-01", self.trace_id(), hex(&self.parent_id))
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, Span};
+use prost_types::Timestamp;
+
+use crate::{
+    analysis::{record::RetrievalRecord, tree::SpanTree},
+    cleanup,
+    config::Collector,
+    jaeger, otlp, Host,
+};
+
+// where kubo on the host exports its spans to during a run
+#[derive(Debug)]
+pub enum Traces {
+    Jaeger {
+        client: jaeger::Client,
+        // stored traces are only cleared by restarting the container
+        restart: cleanup::Guard,
+    },
+    Embedded(otlp::Receiver),
+}
+
+impl Traces {
+    pub async fn start(collector: Collector, host: &Host) -> anyhow::Result<Self> {
+        Ok(match collector {
+            Collector::Jaeger => Self::Jaeger {
+                client: jaeger::Client::on(host).await?,
+                restart: cleanup::register(host, "docker restart $(docker ps -q)"),
+            },
+            Collector::Embedded => Self::Embedded(otlp::Receiver::start(host).await?),
+        })
+    }
+
+    pub async fn save_trace(
+        &self,
+        trace_id: &str,
+        dir: impl AsRef<Path>,
+    ) -> anyhow::Result<PathBuf> {
+        match self {
+            Self::Jaeger { client, .. } => client.save_trace(trace_id, dir).await,
+            Self::Embedded(receiver) => receiver.save_trace(trace_id, dir).await,
+        }
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        match self {
+            Self::Jaeger { restart, .. } => {
+                println!("* Restart telemetry collector");
+                restart.run().await
+            }
+            Self::Embedded(receiver) => {
+                println!("* Stop OTLP receiver");
+                receiver.close().await
+            }
+        }
+    }
+}
+
+// attribute naming the service that reported a span
+pub const SERVICE_ATTR: &str = "service.name";
+
+// spans are saved without their resource, so each keeps the service of its resource as attribute
+pub fn flatten(resource_spans: Vec<ResourceSpans>) -> impl Iterator<Item = Span> {
+    resource_spans.into_iter().flat_map(|resource_spans| {
+        let service = resource_spans
+            .resource
+            .as_ref()
+            .and_then(|resource| {
+                resource
+                    .attributes
+                    .iter()
+                    .find(|attribute| attribute.key == SERVICE_ATTR)
+            })
+            .cloned();
+        resource_spans
+            .scope_spans
+            .into_iter()
+            .flat_map(|scope_spans| scope_spans.spans)
+            .map(move |mut span| {
+                if let Some(service) = &service {
+                    if !span
+                        .attributes
+                        .iter()
+                        .any(|attribute| attribute.key == SERVICE_ATTR)
+                    {
+                        span.attributes.push(service.clone())
+                    }
+                }
+                span
+            })
+    })
+}
+
+// earliest start and latest end, in nanoseconds since UNIX epoch
+pub fn range(spans: &[Span]) -> (u64, u64) {
+    let start = spans
+        .iter()
+        .map(|span| span.start_time_unix_nano)
+        .min()
+        .unwrap_or_default();
+    let end = spans
+        .iter()
+        .map(|span| span.end_time_unix_nano)
+        .max()
+        .unwrap_or_default();
+    (start, end)
+}
+
+// `nanos` since UNIX epoch, saturating
+pub fn timestamp(nanos: u64) -> Timestamp {
+    let time = UNIX_EPOCH
+        .checked_add(Duration::from_nanos(nanos))
+        .unwrap_or(UNIX_EPOCH + Duration::from_secs(253402300799)); // 9999-12-31T23:59:59Z
+    time.into()
+}
+
+// a trace, and the spans of other traces in its time range, e.g. the DHT lookups that the traced
+// operation waits on, saved as `<start time>.json` and `<start time>_other.json` of OTLP JSON
+// spans in `dir`, and for a retrieval the spans it links as `<start time>.record.json`
+pub async fn save(
+    trace_id: &str,
+    trace_spans: &[Span],
+    other_spans: &[Span],
+    dir: impl AsRef<Path>,
+) -> anyhow::Result<PathBuf> {
+    let (start, end) = range(trace_spans);
+    println!("* Total span number {}", trace_spans.len());
+    println!(
+        "* Trace {trace_id} start {} end {}",
+        timestamp(start),
+        timestamp(end)
+    );
+    println!("* Other in time range span number {}", other_spans.len());
+    let dir = dir.as_ref();
+    tokio::fs::create_dir_all(dir).await?;
+    let path = dir.join(format!("{}.json", timestamp(start)));
+    tokio::fs::write(&path, serde_json::to_vec(trace_spans)?).await?;
+    tokio::fs::write(
+        dir.join(format!("{}_other.json", timestamp(start))),
+        serde_json::to_vec(other_spans)?,
+    )
+    .await?;
+    if let Some(record) = RetrievalRecord::link(
+        &SpanTree::new(trace_spans.iter().cloned()),
+        &SpanTree::new(other_spans.iter().cloned()),
+    ) {
+        record.save(RetrievalRecord::path(&path)).await?
+    }
+    Ok(path)
+}
@@ -0,0 +1,364 @@
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use crate::{ready::Probe, EphemeralPeer};
+
+// how a multiaddr is dialed, relay and DNS first since they wrap the other transports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Class {
+    // `/p2p-circuit`
+    Relay,
+    // `/dns`, `/dns4`, `/dns6` or `/dnsaddr`
+    Dns,
+    WebRtcDirect,
+    WebTransport,
+    QuicV1,
+    Tcp,
+    // e.g. websockets or QUIC draft-29
+    Other,
+}
+
+impl Class {
+    pub const ALL: [Self; 7] = [
+        Self::Relay,
+        Self::Dns,
+        Self::WebRtcDirect,
+        Self::WebTransport,
+        Self::QuicV1,
+        Self::Tcp,
+        Self::Other,
+    ];
+
+    pub fn of(addr: &str) -> Self {
+        let mut protocols = addr.split('/');
+        if !addr.contains("/p2p-circuit") && protocols.any(|protocol| protocol.starts_with("dns")) {
+            Self::Dns
+        } else {
+            Self::transport(addr)
+        }
+    }
+
+    // the class of what `addr` dials, i.e. of the addresses that its DNS name resolves to
+    fn transport(addr: &str) -> Self {
+        let protocols = addr.split('/').collect::<Vec<_>>();
+        let has = |protocol| protocols.contains(&protocol);
+        if has("p2p-circuit") {
+            Self::Relay
+        } else if has("webrtc-direct") {
+            Self::WebRtcDirect
+        } else if has("webtransport") {
+            Self::WebTransport
+        } else if has("quic-v1") {
+            Self::QuicV1
+        } else if has("tcp") && !has("ws") && !has("wss") && !has("tls") {
+            Self::Tcp
+        } else {
+            Self::Other
+        }
+    }
+
+    // whether a connection with the remote address `remote` was dialed with `addrs` of this class,
+    // DNS names are resolved before dialing so those connections have the resolved address
+    fn matches(self, remote: &str, addrs: &[String]) -> bool {
+        match self {
+            Self::Dns => addrs.iter().any(|addr| {
+                // `/dnsaddr` resolves to whole multiaddrs, of any transport
+                addr.split('/').any(|protocol| protocol == "dnsaddr")
+                    || Self::transport(addr) == Self::transport(remote)
+            }),
+            class => Self::of(remote) == class,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Relay => "relay",
+            Self::Dns => "dns",
+            Self::WebRtcDirect => "webrtc-direct",
+            Self::WebTransport => "webtransport",
+            Self::QuicV1 => "quic-v1",
+            Self::Tcp => "tcp",
+            Self::Other => "other",
+        }
+    }
+}
+
+// loopback or private, i.e. not reachable from the host
+pub fn is_local(addr: &str) -> bool {
+    addr.strip_prefix("/ip4/")
+        .and_then(|addr| addr.split_once('/'))
+        .and_then(|(ip, _)| ip.parse::<Ipv4Addr>().ok())
+        .map(|ip| ip.is_loopback() || ip.is_private())
+        .unwrap_or(false)
+        || addr
+            .strip_prefix("/ip6/")
+            .and_then(|addr| addr.split_once('/'))
+            .and_then(|(ip, _)| ip.parse::<Ipv6Addr>().ok())
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
+
+// one `ipfs swarm connect` with all addresses of a class
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub class: Class,
+    pub addrs: Vec<String>,
+    // connected, over an address of the class
+    pub success: bool,
+    // connected before dialing, e.g. as a bootstrap peer, so the attempt measures nothing
+    pub preconnected: bool,
+    // of the connection if any, which may be of another class than dialed
+    pub remote: Option<String>,
+    // timed on the host, so without the ssh round trip
+    pub duration: Duration,
+}
+
+impl Attempt {
+    pub const HEADER: &str = "class,addrs,success,preconnected,remote,seconds";
+
+    pub fn fields(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.class.name(),
+            self.addrs.join(" "),
+            self.success,
+            self.preconnected,
+            self.remote.as_deref().unwrap_or_default(),
+            self.duration.as_secs_f32()
+        )
+    }
+}
+
+// remote address of the connection of `peer` to `id`
+async fn remote(peer: &EphemeralPeer, id: &str) -> anyhow::Result<Option<String>> {
+    let suffix = format!("/p2p/{id}");
+    Ok(peer
+        .output("ipfs swarm peers")
+        .await?
+        .check()?
+        .lines()
+        .map(str::trim)
+        .find(|line| line.ends_with(&suffix))
+        .map(Into::into))
+}
+
+// defines `now`, which prints nanoseconds since the epoch with GNU date, or else with perl, e.g. on
+// macOS whose date has no `%N`
+const NOW: &str = "now() { now=$(date +%s%N); case $now in *N) \
+                   perl -MTime::HiRes=time -e 'printf \"%.0f\\n\", time * 1e9' ;; \
+                   *) echo $now ;; esac; }";
+
+// a fresh identity, with a fresh peerstore as kubo keeps it in memory
+async fn restart(peer: &mut EphemeralPeer, probe: &Probe) -> anyhow::Result<()> {
+    peer.shutdown().await?;
+    peer.rotate_identity(false).await?;
+    peer.start_with(probe).await?;
+    Ok(())
+}
+
+// connects `peer` to provider `id` with the addresses of each class in turn, `addrs` end with
+// `/p2p/<id>`; libp2p dials every address it knows for a peer, including those of earlier
+// classes and those learned by identify, so the daemon is restarted with a fresh identity before
+// each class but the first and after the last, which fails with `NotReady` if it does not come up;
+// a class is not dialed if `id` is already connected, e.g. as a bootstrap peer, but recorded as
+// preconnected
+pub async fn connect_each(
+    peer: &mut EphemeralPeer,
+    id: &str,
+    addrs: &[String],
+    timeout: Duration,
+    probe: &Probe,
+) -> anyhow::Result<Vec<Attempt>> {
+    let mut attempts = Vec::new();
+    for class in Class::ALL {
+        let class_addrs = addrs
+            .iter()
+            .filter(|addr| Class::of(addr) == class)
+            .cloned()
+            .collect::<Vec<_>>();
+        if class_addrs.is_empty() {
+            continue;
+        }
+        if !attempts.is_empty() {
+            restart(peer, probe).await?
+        }
+        if let Some(remote) = remote(peer, id).await? {
+            attempts.push(Attempt {
+                class,
+                addrs: class_addrs,
+                success: false,
+                preconnected: true,
+                remote: Some(remote),
+                duration: Duration::ZERO,
+            });
+            continue;
+        }
+        let output = peer
+            .host()
+            .output(format!(
+                "export IPFS_PATH={}; {NOW}; start=$(now); \
+                 timeout -s SIGINT {}s ipfs swarm connect {} > /dev/null; status=$?; \
+                 echo $start $(now); exit $status",
+                peer.repo(),
+                timeout.as_secs(),
+                class_addrs.join(" ")
+            ))
+            .await?;
+        let times = output
+            .stdout
+            .split_whitespace()
+            .map(str::parse::<u64>)
+            .collect::<Result<Vec<_>, _>>();
+        let Ok([start, end]) = times.as_deref() else {
+            anyhow::bail!(
+                "no nanosecond clock on {}, which needs GNU date or perl: {:?} {}",
+                peer.host().name(),
+                output.stdout,
+                output.stderr.trim()
+            )
+        };
+        let duration = Duration::from_nanos(end.saturating_sub(*start));
+        let remote = if output.success() {
+            remote(peer, id).await?
+        } else {
+            None
+        };
+        attempts.push(Attempt {
+            class,
+            success: remote
+                .as_deref()
+                .is_some_and(|remote| class.matches(remote, &class_addrs)),
+            preconnected: false,
+            addrs: class_addrs,
+            remote,
+            duration,
+        })
+    }
+    if !attempts.is_empty() {
+        restart(peer, probe).await?
+    }
+    Ok(attempts)
+}
+
+// success rate and median connect latency per class, without preconnected attempts
+pub fn summarize<'a>(attempts: impl IntoIterator<Item = &'a Attempt>) {
+    let mut classes = BTreeMap::<Class, (usize, Vec<Duration>)>::new();
+    for attempt in attempts {
+        if attempt.preconnected {
+            continue;
+        }
+        let (count, durations) = classes.entry(attempt.class).or_default();
+        *count += 1;
+        if attempt.success {
+            durations.push(attempt.duration)
+        }
+    }
+    for (class, (count, mut durations)) in classes {
+        durations.sort();
+        println!(
+            "* {} connected {}/{count} ({:.1}%){}",
+            class.name(),
+            durations.len(),
+            durations.len() as f64 / count as f64 * 100.,
+            durations
+                .get(durations.len() / 2)
+                .map(|median| format!(" median {median:?}"))
+                .unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "12D3KooWDpJ7As7BWAwRMfu1VU2WCqNjvq387JEYKDBj4kx6nXTN";
+
+    #[test]
+    fn classes() {
+        for (addr, class, transport) in [
+            ("/ip4/1.2.3.4/tcp/4001", Class::Tcp, Class::Tcp),
+            (
+                "/ip4/1.2.3.4/udp/4001/quic-v1",
+                Class::QuicV1,
+                Class::QuicV1,
+            ),
+            ("/ip6/2001:db8::1/udp/4001/quic", Class::Other, Class::Other),
+            ("/dns4/example.com/tcp/443/wss", Class::Dns, Class::Other),
+            ("/dns4/example.com/tcp/4001/ws", Class::Dns, Class::Other),
+            ("/dns4/example.com/tcp/4001", Class::Dns, Class::Tcp),
+            (
+                "/ip4/1.2.3.4/udp/4001/quic-v1/webtransport/certhash/uEiA",
+                Class::WebTransport,
+                Class::WebTransport,
+            ),
+            (
+                "/ip4/1.2.3.4/udp/4001/webrtc-direct/certhash/uEiA",
+                Class::WebRtcDirect,
+                Class::WebRtcDirect,
+            ),
+            (
+                &format!("/ip4/1.2.3.4/tcp/4001/p2p/{ID}/p2p-circuit"),
+                Class::Relay,
+                Class::Relay,
+            ),
+            (
+                &format!("/dns4/relay.example.com/tcp/4001/p2p/{ID}/p2p-circuit"),
+                Class::Relay,
+                Class::Relay,
+            ),
+            ("/dnsaddr/bootstrap.libp2p.io", Class::Dns, Class::Other),
+        ] {
+            assert_eq!(Class::of(addr), class, "{addr}");
+            assert_eq!(Class::transport(addr), transport, "{addr}");
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let addrs = |addrs: &[&str]| {
+            addrs
+                .iter()
+                .map(|addr| format!("{addr}/p2p/{ID}"))
+                .collect::<Vec<_>>()
+        };
+        let tcp = format!("/ip4/1.2.3.4/tcp/4001/p2p/{ID}");
+        let quic = format!("/ip4/1.2.3.4/udp/4001/quic-v1/p2p/{ID}");
+        let relayed = format!("/ip4/5.6.7.8/tcp/4001/p2p/{ID}/p2p-circuit/p2p/{ID}");
+
+        assert!(Class::Tcp.matches(&tcp, &addrs(&["/ip4/1.2.3.4/tcp/4001"])));
+        assert!(!Class::Tcp.matches(&quic, &addrs(&["/ip4/1.2.3.4/tcp/4001"])));
+        assert!(Class::Relay.matches(&relayed, &addrs(&["/ip4/5.6.7.8/tcp/4001"])));
+        assert!(!Class::Relay.matches(&tcp, &addrs(&["/ip4/5.6.7.8/tcp/4001"])));
+
+        let dns = addrs(&["/dns4/example.com/tcp/4001"]);
+        assert!(Class::Dns.matches(&tcp, &dns));
+        assert!(!Class::Dns.matches(&quic, &dns));
+        let dnsaddr = addrs(&["/dnsaddr/bootstrap.libp2p.io"]);
+        assert!(Class::Dns.matches(&tcp, &dnsaddr));
+        assert!(Class::Dns.matches(&quic, &dnsaddr));
+    }
+
+    #[test]
+    fn local() {
+        for addr in [
+            "/ip4/127.0.0.1/tcp/4001",
+            "/ip4/10.0.0.1/tcp/4001",
+            "/ip4/192.168.1.1/udp/4001/quic-v1",
+            "/ip6/::1/tcp/4001",
+        ] {
+            assert!(is_local(addr), "{addr}");
+        }
+        for addr in [
+            "/ip4/1.2.3.4/tcp/4001",
+            "/ip6/2001:db8::1/tcp/4001",
+            "/dns4/localhost/tcp/4001",
+            "/dnsaddr/bootstrap.libp2p.io",
+        ] {
+            assert!(!is_local(addr), "{addr}");
+        }
+    }
+}